@@ -1,22 +1,31 @@
 mod util;
 use util::{
     event::{ Event, Events },
-    signal::StatefulList,
+    cli,
+    config::{ self, UserConfig },
+    export::{ self, Format },
+    storage,
+    app::{ App, Criterion, InputMode },
+    editor::EditorField,
+    keymap::Action,
+    sequence::Parsed,
     draw::{
-        App,
-        Card,
-        InputMode,
+        draw_archive,
+        draw_confirm_delete,
+        draw_confirm_delete_board,
+        draw_filters,
+        draw_help,
         draw_help_text, 
         draw_input_box, 
         draw_lanes, 
+        draw_tabs,
         draw_description
     }
 };
 
-use std::{ error::Error, io };
+use std::{ error::Error, io, path::{ Path, PathBuf }, process, time::SystemTime };
 
 use termion::{
-    event::Key,
     raw::IntoRawMode,
     screen::AlternateScreen
 };
@@ -29,11 +38,56 @@ use tui::{
 
 // Not sure why Box<dyn Error>> instead of just io::Error??
 fn main() -> Result<(), Box<dyn Error>> {
-    // Create the app and default lanes:
-    let mut app = App::default();
-    for _ in 0..4 { app.lanes.push(StatefulList::with_items(Vec::new())); }
-    // Listen for events:
-    let events = Events::new();
+    let args = cli::command().get_matches();
+    // Read the keybindings first, so a broken config is reported
+    // before the terminal goes into raw mode:
+    let config_path = match args.value_of_os("config") {
+        // A config file that was asked for has to be there
+        Some(path) if !PathBuf::from(path).exists() => {
+            eprintln!("There is no config file at {}", PathBuf::from(path).display());
+            process::exit(1);
+        },
+        Some(path) => PathBuf::from(path),
+        None => config::default_path(),
+    };
+    let user_config = match UserConfig::load(&config_path) {
+        Ok(user_config) => user_config,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
+    };
+    // Load the saved boards, or create the app and default lanes:
+    let board_path = args.value_of_os("board").map_or_else(storage::default_path, PathBuf::from);
+    let mut app = match storage::load(&board_path)? {
+        Some(saved) => App::with_boards(saved.names, saved.boards, saved.open),
+        None => App::default(),
+    };
+    app.keymap = user_config.keymap;
+    app.set_undo_depth(user_config.undo_depth);
+    app.show_ids = user_config.show_ids;
+    app.readonly = args.is_present("readonly");
+
+    // Subcommands do their thing without opening the board in the terminal:
+    if let (name, Some(sub_args)) = args.subcommand() {
+        if let Err(err) = cli::run(&mut app, name, sub_args) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        if app.dirty && !app.readonly {
+            storage::save(&board_path, &app)?;
+        }
+        return Ok(());
+    }
+
+    // The board file as it was last read or written, to notice when
+    // something else (like a hook running `add`) changes it:
+    let mut board_modified = storage::modified(&board_path);
+
+    // Listen for events. Quitting is up to the keymap, so 'q' can
+    // still be typed into a title:
+    let mut events = Events::new();
+    events.disable_exit_key();
     // The double stdout is what the actual documentation suggests
     let stdout = io::stdout().into_raw_mode()?;
     let stdout = AlternateScreen::from(stdout);
@@ -42,6 +96,11 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     loop {
         terminal.draw(|f| {
+            if let InputMode::Help = app.input_mode {
+                draw_help(f, f.size(), &mut app);
+                return;
+            }
+
             let main_layout = Layout::default()
                 .direction(Direction::Vertical)
                 .margin(1)
@@ -50,6 +109,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                     Constraint::Percentage(8),
                     // Search Bar
                     Constraint::Percentage(12),
+                    // Board tabs
+                    Constraint::Length(3),
                     // Lanes and Cards
                     Constraint::Percentage(50),
                     // Description
@@ -60,13 +121,12 @@ fn main() -> Result<(), Box<dyn Error>> {
             let card_layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .margin(1)
-                .constraints([
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(25),
-                ].as_ref())
-                .split(main_layout[2]);
+                // One equal width column per lane:
+                .constraints(vec![
+                    Constraint::Ratio(1, app.lanes.len() as u32);
+                    app.lanes.len()
+                ])
+                .split(main_layout[3]);
 
             let description_layout = Layout::default()
                 .direction(Direction::Horizontal)
@@ -75,18 +135,56 @@ fn main() -> Result<(), Box<dyn Error>> {
                     Constraint::Percentage(75),
                     Constraint::Percentage(25),
                 ].as_ref())
-                .split(main_layout[3]);
+                .split(main_layout[4]);
 
             draw_help_text(f, main_layout[0], &app);
             draw_input_box(f, main_layout[1], &app);
-            draw_lanes(f, card_layout, &mut app);
-            draw_description(f, description_layout, &app);
+            draw_tabs(f, main_layout[2], &app);
+            match app.input_mode {
+                // The archive takes the place of the lanes and description:
+                InputMode::Archive |
+                InputMode::ArchiveSearch => {
+                    let archive_chunk = main_layout[3].union(main_layout[4]);
+                    draw_archive(f, archive_chunk, &mut app);
+                },
+                _ => {
+                    draw_lanes(f, card_layout, &mut app);
+                    draw_description(f, description_layout, &mut app);
+                },
+            }
+            if let InputMode::ConfirmDelete(count) = app.input_mode {
+                draw_confirm_delete(f, f.size(), &app, count);
+            }
+            if let InputMode::ConfirmDeleteBoard = app.input_mode {
+                draw_confirm_delete_board(f, f.size(), &app);
+            }
+            if let InputMode::Filters = app.input_mode {
+                draw_filters(f, main_layout[3], &mut app);
+            }
 
-            // Display the cursor if in Title or Description mode
+            // Display the cursor in any of the typing modes
             match app.input_mode {
-                InputMode::Normal => {},
+                InputMode::Normal |
+                InputMode::Edit |
+                InputMode::Checklist |
+                InputMode::Help |
+                InputMode::ConfirmDelete(_) |
+                InputMode::ConfirmDeleteBoard |
+                InputMode::Archive |
+                InputMode::Filters => {},
                 InputMode::Title |
-                InputMode::Description => {
+                InputMode::Description |
+                InputMode::Smart(_) |
+                InputMode::NewLane |
+                InputMode::RenameLane |
+                InputMode::NewBoard |
+                InputMode::RenameBoard |
+                InputMode::ExportPath |
+                InputMode::ImportPath |
+                InputMode::EditField |
+                InputMode::ArchiveSearch |
+                InputMode::Search |
+                InputMode::FilterName => {
                     f.set_cursor(
                         // Put cursor past the end of the input text
                         main_layout[1].x + app.input.len() as u16 + 1,
@@ -97,124 +195,430 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
         })?; // End closure
 
+        // Pick up the board file if something else changed it, unless
+        // there is a card being edited or a change waiting to be saved:
+        let event = events.next().unwrap();
+        if let Event::Tick = event {
+            if app.input_mode == InputMode::Normal && !app.dirty && changed_since(&board_path, board_modified) {
+                reload(&mut app, &board_path, "The board file changed, reloaded it");
+                board_modified = storage::modified(&board_path);
+            }
+        }
+
         // Handle input
-        if let Event::Input(input) = events.next().unwrap() {
+        if let Event::Input(input) = event {
+            app.message = None;
+            let mode = app.keymap_mode();
+            let (action, count) = match app.sequence.push(&app.keymap, mode, input) {
+                // Wait for the rest of the count or sequence:
+                Parsed::Pending => { continue; },
+                Parsed::Action(action, count) => (Some(action), count),
+                Parsed::Unbound(_) => (None, None),
+            };
+            // Most actions are repeated by a count, e.g. 3j
+            let times = count.unwrap_or(1);
             match app.input_mode {
-                InputMode::Normal => match input {
-                    // Needs to be first:
-                    Key::Char('\n') => {
-                        // Select the current card
-                        // for editing title / description
-                    },
-                    // Display the help screen
-                    Key::Char('?') => { },
-                    Key::Char('q') => { break; },
-                    Key::Char('t') => { app.input_mode = InputMode::Title }, 
-                    Key::Char('d') => { app.input_mode = InputMode::Description },
-
-                    Key::Up => { app.lanes[app.current_lane].previous(); },
-                    Key::Down => { app.lanes[app.current_lane].next(); },
-                    
-                    Key::Left => {
-                        if app.current_lane != 0 {
-                            app.lanes[app.current_lane].unselect();
-                            app.current_lane -= 1;
-                            app.lanes[app.current_lane].next();
+                InputMode::Normal => match action {
+                    Some(Action::Quit) => { break; },
+                    Some(Action::Help) => { app.open_help(); },
+                    Some(Action::NewCard) => { app.input_mode = InputMode::Title }, 
+                    Some(Action::AddDescription) => { app.input_mode = InputMode::Description },
+                    Some(Action::EditCard) => { app.open_editor(); },
+                    Some(Action::OpenChecklist) => { app.open_checklist(); },
+
+                    Some(Action::PreviousCard) => { for _ in 0..times { app.previous_card(); } },
+                    Some(Action::NextCard) => { for _ in 0..times { app.next_card(); } },
+                    // With a count these go to that card, like 3gg or 3G
+                    Some(Action::FirstCard) => { app.select_card(count.unwrap_or(1) - 1); },
+                    Some(Action::LastCard) => { app.select_card(count.map_or(usize::MAX, |n| n - 1)); },
+                    Some(Action::PreviousLane) => { for _ in 0..times { app.previous_lane(); } },
+                    Some(Action::NextLane) => { for _ in 0..times { app.next_lane(); } },
+                    Some(Action::MoveCardLeft) => { app.move_card(-(times as isize)); },
+                    Some(Action::MoveCardRight) => { app.move_card(times as isize); },
+                    Some(Action::MoveCardUp) => { for _ in 0..times { app.shift_card(-1); } },
+                    Some(Action::MoveCardDown) => { for _ in 0..times { app.shift_card(1); } },
+                    Some(Action::CycleSort) => { app.cycle_sort(); },
+                    Some(Action::ToggleIds) => { app.show_ids = !app.show_ids; },
+                    Some(Action::RaisePriority) => { for _ in 0..times { app.step_priority(1); } },
+                    Some(Action::LowerPriority) => { for _ in 0..times { app.step_priority(-1); } },
+
+                    // Cutting, copying and pasting cards:
+                    Some(Action::DeleteCard) => { app.ask_delete(times); },
+                    Some(Action::ArchiveCard) => { for _ in 0..times { app.archive_card(); } },
+                    Some(Action::OpenArchive) => { app.open_archive(); },
+
+                    // Searching:
+                    Some(Action::Search) => {
+                        app.input = app.search.clone();
+                        app.input_mode = InputMode::Search;
+                    },
+                    Some(Action::NextHit) => { for _ in 0..times { app.next_hit(true); } },
+                    Some(Action::PreviousHit) => { for _ in 0..times { app.next_hit(false); } },
+                    Some(Action::ClearSearch) => { app.set_search(""); },
+                    Some(Action::SaveFilter) => {
+                        if app.search.is_empty() {
+                            app.message = Some(String::from("Search for something first, then save it"));
+                        } else {
+                            app.input_mode = InputMode::FilterName;
                         }
+                    },
+                    Some(Action::OpenFilters) => { app.open_filters(); },
+                    Some(Action::CopyCard) => { app.copy_card(); },
+                    Some(Action::PasteCard) => { for _ in 0..times { app.paste_card(); } },
+                    Some(Action::Undo) => { for _ in 0..times { app.undo(); } },
+                    Some(Action::Redo) => { for _ in 0..times { app.redo(); } },
+
+                    // Measurable progress:
+                    Some(Action::ProgressUp) => { app.step_progress(times as i64); },
+                    Some(Action::ProgressDown) => { app.step_progress(-(times as i64)); },
+                    Some(Action::ScrollDescriptionUp) => { app.scroll_description(-5 * times as isize); },
+                    Some(Action::ScrollDescriptionDown) => { app.scroll_description(5 * times as isize); },
 
+                    // Lanes:
+                    Some(Action::AddLane) => { app.input_mode = InputMode::NewLane },
+                    Some(Action::RenameLane) => {
+                        app.input = app.lanes[app.current_lane].name.clone();
+                        app.input_mode = InputMode::RenameLane;
                     },
-                    Key::Right => {
-                        if app.current_lane != 3 {
-                            app.lanes[app.current_lane].unselect();
-                            app.current_lane += 1;
-                            app.lanes[app.current_lane].next();
+                    Some(Action::RemoveLane) => { app.remove_lane(); },
+                    Some(Action::MoveLaneLeft) => { app.move_lane(-1); },
+                    Some(Action::MoveLaneRight) => { app.move_lane(1); },
+
+                    // Boards:
+                    Some(Action::NextBoard) => match count {
+                        Some(tab) => { app.switch_board(tab.saturating_sub(1)); },
+                        None => { app.next_board(); },
+                    },
+                    Some(Action::PreviousBoard) => { for _ in 0..times { app.previous_board(); } },
+                    Some(Action::NewBoard) => { app.input_mode = InputMode::NewBoard; },
+                    Some(Action::RenameBoard) => {
+                        app.input = app.board_name().to_string();
+                        app.input_mode = InputMode::RenameBoard;
+                    },
+                    Some(Action::DeleteBoard) => { app.ask_delete_board(); },
+                    Some(Action::ExportBoard) => {
+                        app.input = export::default_path(&app, Format::Markdown);
+                        app.input_mode = InputMode::ExportPath;
+                    },
+                    Some(Action::ImportBoard) => {
+                        app.input = export::default_path(&app, Format::Markdown);
+                        app.input_mode = InputMode::ImportPath;
+                    },
+                    _ => { },
+                },
+
+                InputMode::Title => match action {
+                    Some(Action::Submit) => {
+                        // Submitting in Title mode will walk through
+                        // the SMART criteria, unless app.input.len() < 7
+                        if app.input.len() > 6 {
+                            // Create the card, it is selected so the
+                            // criteria and description go onto it:
+                            let title = std::mem::take(&mut app.input);
+                            app.add_card(&title);
+                            app.input_mode = InputMode::Smart(Criterion::Specific);
+                        } else {
+                            app.message = Some(String::from("Titles need at least 7 characters"));
                         }
                     },
-                    
-                    Key::Ctrl(',') => {
-                        if app.current_lane != 0 {
-                            // Get the card that is currently selected:
-                            let current_index = app.lanes[app.current_lane]
-                                .state.selected().unwrap();
-
-                            if let Some(current_card) = app.get_current_card(){
-                                // Push the card to the previous lane:
-                                app.lanes[app.current_lane-1].items.push(current_card);
-                                // Unselect and Remove from the current lane:
-                                app.lanes[app.current_lane].unselect();
-                                app.lanes[app.current_lane].items.remove(current_index);
-                                // Switch to that lane:
-                                app.current_lane -= 1; 
-                                // Select the 'next' card in that lane
-                                app.lanes[app.current_lane].next();
-                            }
+                    Some(Action::Cancel) => { app.input_mode = InputMode::Normal; },
+                    _ => { app.type_key(input); },
+                },
+
+                InputMode::Smart(criterion) => match action {
+                    Some(Action::Submit) => {
+                        // An empty answer skips the criterion
+                        let value = std::mem::take(&mut app.input);
+                        app.set_smart(criterion, &value);
+                        app.input_mode = match criterion.next() {
+                            Some(next) => InputMode::Smart(next),
+                            None => InputMode::Description,
+                        };
+                    },
+                    Some(Action::Cancel) => {
+                        app.input = "".to_string();
+                        app.input_mode = InputMode::Normal;
+                    },
+                    _ => { app.type_key(input); },
+                },
+
+                InputMode::Description => match action {
+                    Some(Action::Submit) => {
+                        let line = std::mem::take(&mut app.input);
+                        if !app.add_description_line(&line) {
+                            app.input = line;
+                            app.message = Some(String::from("Select a card to describe first"));
                         }
                     },
-                    Key::Ctrl('.') => {
-                        if app.current_lane != 3 {
-                            // Get the card that is currently selected:
-                            let current_index = app.lanes[app.current_lane]
-                                .state.selected().unwrap();
-
-                            if let Some(current_card) = app.get_current_card(){
-                                // Push the card to the next lane:
-                                app.lanes[app.current_lane+1].items.push(current_card);
-                                // Unselect and Remove from the current lane:
-                                app.lanes[app.current_lane].unselect();
-                                app.lanes[app.current_lane].items.remove(current_index);
-                                // Switch to that lane:
-                                app.current_lane += 1; 
-                                // Select the 'next' card in that lane
-                                app.lanes[app.current_lane].next();
+                    Some(Action::Cancel) => { app.input_mode = InputMode::Normal; },
+                    _ => { app.type_key(input); },
+                },
+
+                InputMode::NewLane |
+                InputMode::RenameLane => match action {
+                    Some(Action::Submit) => {
+                        let name = app.input.trim().to_string();
+                        if name.is_empty() {
+                            app.message = Some(String::from("Lanes need a name"));
+                        } else {
+                            if let InputMode::NewLane = app.input_mode {
+                                app.add_lane(&name);
+                            } else {
+                                app.rename_lane(&name);
                             }
+                            app.input = "".to_string();
+                            app.input_mode = InputMode::Normal;
                         }
                     },
+                    Some(Action::Cancel) => {
+                        app.input = "".to_string();
+                        app.input_mode = InputMode::Normal;
+                    },
+                    _ => { app.type_key(input); },
+                },
+
+                InputMode::Checklist => match action {
+                    Some(Action::ToggleItem) => { app.toggle_line(); },
+                    Some(Action::ToggleCheckbox) => { app.toggle_line_checkbox(); },
+                    Some(Action::PreviousLine) => { for _ in 0..times { app.previous_line(); } },
+                    Some(Action::NextLine) => { for _ in 0..times { app.next_line(); } },
+                    Some(Action::CloseChecklist) => {
+                        app.checklist.select(None);
+                        app.input_mode = InputMode::Normal;
+                    },
                     _ => { },
                 },
 
-                InputMode::Title => match input {
-                    Key::Char('\n') => {
-                        // Pressing enter in Title mode will switch to
-                        // Description mode, unless app.input.len() < 7
-                        print!("{:?}", app.current_lane);
+                InputMode::Edit => if let Some(editor) = app.editor.as_mut() {
+                    match action {
+                        Some(Action::EditField) => {
+                            app.input = editor.value();
+                            app.input_mode = InputMode::EditField;
+                        },
+                        Some(Action::InsertLineBelow) => {
+                            editor.insert_line(false);
+                            app.input_mode = InputMode::EditField;
+                        },
+                        Some(Action::InsertLineAbove) => {
+                            editor.insert_line(true);
+                            app.input_mode = InputMode::EditField;
+                        },
+                        Some(Action::DeleteLine) => { editor.delete_line(); },
+                        Some(Action::PriorityUp) => { editor.raise_priority(); },
+                        Some(Action::PriorityDown) => { editor.lower_priority(); },
+                        Some(Action::PreviousField) => { for _ in 0..times { editor.previous(); } },
+                        Some(Action::NextField) => { for _ in 0..times { editor.next(); } },
+                        Some(Action::CloseEditor) => { app.close_editor(); },
+                        _ => { },
+                    }
+                },
 
-                        if app.input.len() > 6 {
-                            // Create the card
-                            let new_card = Card {
-                                title: String::from(&app.input),
-                                description: Vec::new(),
-                                lane: 0,
-                                priority: 0,
-                            };
-                            app.lanes[app.current_lane].items.push(new_card);
+                InputMode::EditField => if let Some(editor) = app.editor.as_mut() {
+                    match action {
+                        Some(Action::Submit) => {
+                            if editor.set_value(&app.input) {
+                                app.input = "".to_string();
+                                app.input_mode = InputMode::Edit;
+                            } else {
+                                app.message = Some(format!("'{}' isn't a valid value here", app.input));
+                            }
+                        },
+                        Some(Action::Cancel) => {
+                            editor.cancel_value();
                             app.input = "".to_string();
-                            if let None = app.get_current_card(){
-                                app.get_current_card();
+                            app.input_mode = InputMode::Edit;
+                        },
+                        Some(Action::CompleteTag) if editor.field() == EditorField::Tags => {
+                            app.complete_tag();
+                        },
+                        _ => { app.type_key(input); },
+                    }
+                },
+
+                InputMode::NewBoard |
+                InputMode::RenameBoard => match action {
+                    Some(Action::Submit) => {
+                        let name = app.input.trim().to_string();
+                        if name.is_empty() {
+                            app.message = Some(String::from("Boards need a name"));
+                        } else {
+                            app.input = "".to_string();
+                            if let InputMode::NewBoard = app.input_mode {
+                                app.input_mode = InputMode::Normal;
+                                app.add_board(&name);
+                            } else {
+                                app.input_mode = InputMode::Normal;
+                                app.rename_board(&name);
                             }
-                            app.input_mode = InputMode::Description;
                         }
                     },
-                    Key::Esc => { app.input_mode = InputMode::Normal; },
-                    Key::Char(c) => { app.input.push(c); },
-                    Key::Backspace => { app.input.pop(); },
+                    Some(Action::Cancel) => {
+                        app.input = "".to_string();
+                        app.input_mode = InputMode::Normal;
+                    },
+                    _ => { app.type_key(input); },
+                },
+
+                InputMode::ExportPath |
+                InputMode::ImportPath => match action {
+                    Some(Action::Submit) => {
+                        let result = if let InputMode::ExportPath = app.input_mode {
+                            export::export_file(&app, &app.input)
+                        } else {
+                            let path = app.input.clone();
+                            export::import_file(&mut app, &path)
+                        };
+                        // A path that didn't work stays in the box to be fixed
+                        match result {
+                            Ok(message) => {
+                                app.input = "".to_string();
+                                app.input_mode = InputMode::Normal;
+                                app.message = Some(message);
+                            },
+                            Err(error) => { app.message = Some(error); },
+                        }
+                    },
+                    Some(Action::Cancel) => {
+                        app.input = "".to_string();
+                        app.input_mode = InputMode::Normal;
+                    },
+                    _ => { app.type_key(input); },
+                },
+
+                InputMode::ConfirmDeleteBoard => match action {
+                    Some(Action::Yes) => {
+                        app.input_mode = InputMode::Normal;
+                        app.delete_board();
+                    },
+                    Some(Action::No) => { app.input_mode = InputMode::Normal; },
                     _ => { },
                 },
 
-                InputMode::Description => match input {
-                    Key::Char('\n') => {
-                        if let Some(mut current_card) = app.get_current_card(){
-                            current_card.description.push(String::from(&app.input));
-                            app.input = "".to_string();
+                InputMode::ConfirmDelete(count) => match action {
+                    Some(Action::Yes) => {
+                        app.delete_cards(count);
+                        app.input_mode = InputMode::Normal;
+                    },
+                    Some(Action::No) => { app.input_mode = InputMode::Normal; },
+                    _ => { },
+                },
+
+                InputMode::Archive => match action {
+                    Some(Action::PreviousArchived) => { for _ in 0..times { app.previous_archived(); } },
+                    Some(Action::NextArchived) => { for _ in 0..times { app.next_archived(); } },
+                    Some(Action::RestoreCard) => { app.restore_card(); },
+                    Some(Action::SearchArchive) => {
+                        app.input = app.archive_filter.clone();
+                        app.input_mode = InputMode::ArchiveSearch;
+                    },
+                    Some(Action::CloseArchive) => { app.input_mode = InputMode::Normal; },
+                    _ => { },
+                },
+
+                InputMode::ArchiveSearch => match action {
+                    Some(Action::Submit) => { app.search_archive(); },
+                    Some(Action::Cancel) => {
+                        app.input = "".to_string();
+                        app.input_mode = InputMode::Archive;
+                    },
+                    _ => {
+                        app.type_key(input);
+                        // The list is filtered as the search is typed
+                        let first = if app.archive_matches().is_empty() { None } else { Some(0) };
+                        app.archive_list.select(first);
+                    },
+                },
+
+                InputMode::Search => match action {
+                    Some(Action::Submit) => {
+                        // A query that doesn't parse stays in the box to be fixed
+                        if let Some(error) = app.filter_error() {
+                            app.message = Some(error);
+                            continue;
+                        }
+                        let search = std::mem::take(&mut app.input);
+                        app.input_mode = InputMode::Normal;
+                        app.set_search(&search);
+                        if !app.search.is_empty() && app.current_card().is_none() {
+                            app.message = Some(format!("No cards match '{}'", app.search));
                         }
                     },
-                    Key::Esc => { app.input_mode = InputMode::Normal; },
-                    Key::Char(c) => { app.input.push(c); },
-                    Key::Backspace => { app.input.pop(); },
+                    // Back out to whatever was searched for before
+                    Some(Action::Cancel) => {
+                        app.input = "".to_string();
+                        app.input_mode = InputMode::Normal;
+                        app.follow_search();
+                    },
+                    _ => {
+                        app.type_key(input);
+                        app.follow_search();
+                    },
+                },
+
+                InputMode::FilterName => match action {
+                    Some(Action::Submit) => {
+                        let name = std::mem::take(&mut app.input);
+                        app.save_filter(&name);
+                        app.input_mode = InputMode::Normal;
+                    },
+                    Some(Action::Cancel) => {
+                        app.input = "".to_string();
+                        app.input_mode = InputMode::Normal;
+                    },
+                    _ => { app.type_key(input); },
+                },
+
+                InputMode::Filters => match action {
+                    Some(Action::PreviousFilter) => { for _ in 0..times { app.previous_filter(); } },
+                    Some(Action::NextFilter) => { for _ in 0..times { app.next_filter(); } },
+                    Some(Action::ApplyFilter) => { app.apply_filter(); },
+                    Some(Action::DeleteFilter) => { app.delete_filter(); },
+                    Some(Action::CloseFilters) => { app.input_mode = InputMode::Normal; },
+                    _ => { },
+                },
+
+                InputMode::Help => match action {
+                    Some(Action::ScrollHelpUp) => { app.scroll_help(-(times as isize)); },
+                    Some(Action::ScrollHelpDown) => { app.scroll_help(times as isize); },
+                    Some(Action::PageHelpUp) => { app.scroll_help(-10); },
+                    Some(Action::PageHelpDown) => { app.scroll_help(10); },
+                    Some(Action::CloseHelp) => { app.input_mode = InputMode::Normal; },
                     _ => { },
                 },
             }
         }
+
+        // Save after every change so nothing is lost on exit, but don't
+        // write over a change made to the file since it was read:
+        if app.dirty && !app.readonly {
+            if changed_since(&board_path, board_modified) {
+                let message = "The board file changed, reloaded it and dropped the last change";
+                reload(&mut app, &board_path, message);
+            } else {
+                storage::save(&board_path, &app)?;
+            }
+            app.dirty = false;
+            board_modified = storage::modified(&board_path);
+        }
     } // loop
 
     Ok(())
 }
+
+// Whether something wrote the board file after `modified`. A deleted
+// file has nothing in it to lose.
+fn changed_since(path: &Path, modified: Option<SystemTime>) -> bool {
+    storage::modified(path).is_some_and(|now| Some(now) != modified)
+}
+
+// Read the boards back from `path` into `app`, saying `message` if that worked
+fn reload(app: &mut App, path: &Path, message: &str) {
+    match storage::load(path) {
+        Ok(Some(saved)) => {
+            app.reload(saved.names, saved.boards);
+            app.message = Some(String::from(message));
+        },
+        Ok(None) => {},
+        Err(err) => { app.message = Some(format!("Could not reload the board file, {}", err)); },
+    }
+}
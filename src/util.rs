@@ -1,3 +1,18 @@
+// event and signal come from the tui-rs examples and are not all used (yet)
+#[allow(dead_code)]
 pub mod event;
+#[allow(dead_code)]
 pub mod signal;
+pub mod app;
+pub mod cli;
+pub mod config;
+pub mod date;
 pub mod draw;
+pub mod editor;
+pub mod export;
+pub mod history;
+pub mod keymap;
+pub mod markdown;
+pub mod query;
+pub mod sequence;
+pub mod storage;
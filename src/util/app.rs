@@ -0,0 +1,2203 @@
+use super::{
+    date,
+    editor::CardEditor,
+    history::{ Change, History },
+    keymap::{ Action, Keymap, Lookup, Mode },
+    query::{ self, Filter, SavedFilter },
+    sequence::KeySequence,
+    signal::{ StatefulList, TabsState },
+};
+
+use std::{ collections::{ HashMap, HashSet }, fmt };
+
+use chrono::{ DateTime, Local, NaiveDate, Utc };
+use rand::Rng;
+use serde::{ Deserialize, Serialize };
+use termion::event::Key;
+use tui::widgets::ListState;
+
+/// The lanes a brand new board starts out with.
+pub const DEFAULT_LANES: [&str; 4] = ["Todo", "In Progress", "Finished", "In review"];
+/// What the first board is called, before it is renamed.
+pub const DEFAULT_BOARD: &str = "Main";
+
+// Card IDs are this many of these, leaving out the ones that are easy to mix up (0/o, 1/l/i):
+const ID_CHARS: &[u8] = b"23456789abcdefghjkmnpqrstuvwxyz";
+const ID_LENGTH: usize = 4;
+
+#[derive(Clone)]
+pub struct App {
+    pub input: String,
+    pub input_mode: InputMode,
+    pub lanes: Vec<Lane>,
+    pub current_lane: usize,
+    // Set whenever the lanes change, so main knows to save the board:
+    pub dirty: bool,
+    // Shown in place of the help text until the next key press:
+    pub message: Option<String>,
+    // The card being edited in InputMode::Edit / EditField:
+    pub editor: Option<CardEditor>,
+    // The selected description line in InputMode::Checklist:
+    pub checklist: ListState,
+    // How many rows of the description are scrolled out of view:
+    pub description_scroll: usize,
+    pub keymap: Keymap,
+    // Counts and multi-key sequences typed so far:
+    pub sequence: KeySequence,
+    // How far the help screen is scrolled:
+    pub help_scroll: usize,
+    // The last card copied or deleted, for pasting:
+    pub register: Option<Card>,
+    // Every change to the lanes, for undo and redo:
+    pub history: History,
+    // Cards taken off the board, oldest first:
+    pub archive: Vec<ArchivedCard>,
+    // The selected card in InputMode::Archive, an index into archive_matches():
+    pub archive_list: ListState,
+    // What the archive is searched for:
+    pub archive_filter: String,
+    // Only cards matching this query are shown, unless it is empty:
+    pub search: String,
+    // Queries saved under a name, kept with the board:
+    pub filters: Vec<SavedFilter>,
+    // The selected filter in InputMode::Filters:
+    pub filter_list: ListState,
+    // The tab bar: every board's name, and which one is open:
+    pub tabs: TabsState,
+    // What is on the boards that aren't open, by tab. The open board's
+    // slot is left empty, as its lanes and so on are the fields above:
+    pub boards: Vec<Board>,
+    // Set by --readonly: nothing can be changed, so nothing is saved.
+    pub readonly: bool,
+    // Whether the lanes show each card's ID:
+    pub show_ids: bool,
+}
+impl Default for App {
+    fn default() -> App {
+        App {
+            input: String::new(),
+            input_mode: InputMode::Normal,
+            lanes: DEFAULT_LANES.iter().map(|name| Lane::new(name)).collect(),
+            current_lane: 0,
+            dirty: false,
+            message: None,
+            editor: None,
+            checklist: ListState::default(),
+            description_scroll: 0,
+            keymap: Keymap::default(),
+            sequence: KeySequence::default(),
+            help_scroll: 0,
+            register: None,
+            history: History::default(),
+            archive: Vec::new(),
+            archive_list: ListState::default(),
+            archive_filter: String::new(),
+            search: String::new(),
+            filters: Vec::new(),
+            filter_list: ListState::default(),
+            tabs: TabsState::new(vec![String::from(DEFAULT_BOARD)]),
+            boards: vec![Board::default()],
+            readonly: false,
+            show_ids: false,
+        }
+    }
+}
+impl App {
+    /// Open board `open` of `boards`, which are named by `names`.
+    pub fn with_boards(names: Vec<String>, boards: Vec<Board>, open: usize) -> App {
+        if boards.is_empty() || names.len() != boards.len() { return App::default(); }
+        let mut app = App {
+            tabs: TabsState { index: open.min(boards.len() - 1), titles: names },
+            boards,
+            ..App::default()
+        };
+        app.unpark();
+        app.assign_ids();
+        app
+    }
+
+    /// Swap in `boards`, read back after something else changed the board
+    /// file, keeping the open tab, the lane and the settings from config.toml.
+    /// The undo history goes, since it was about the old cards.
+    pub fn reload(&mut self, names: Vec<String>, boards: Vec<Board>) {
+        let mut app = App::with_boards(names, boards, self.tabs.index);
+        app.keymap = self.keymap.clone();
+        app.set_undo_depth(self.history.depth());
+        app.show_ids = self.show_ids;
+        app.readonly = self.readonly;
+        app.register = self.register.take();
+        app.show((self.current_lane, self.current_index()));
+        *self = app;
+    }
+
+    // Every card on every board, archived or not
+    fn card_ids(&self) -> HashSet<String> {
+        let boards = std::iter::once((&self.lanes, &self.archive))
+            .chain(self.boards.iter().map(|board| (&board.lanes, &board.archive)));
+        let mut ids = HashSet::new();
+        for (lanes, archive) in boards {
+            let cards = lanes.iter()
+                .flat_map(|lane| lane.cards.items.iter())
+                .chain(archive.iter().map(|entry| &entry.card));
+            ids.extend(cards.map(|card| card.id.clone()));
+        }
+        ids
+    }
+
+    /// An ID that no card has yet.
+    pub fn new_id(&self) -> String {
+        fresh_id(&mut self.card_ids())
+    }
+
+    /// Give cards from before there were IDs (or that share one) an ID of their own.
+    pub fn assign_ids(&mut self) {
+        let mut taken = HashSet::new();
+        let mut assigned = false;
+        let boards = std::iter::once((&mut self.lanes, &mut self.archive))
+            .chain(self.boards.iter_mut().map(|board| (&mut board.lanes, &mut board.archive)));
+        for (lanes, archive) in boards {
+            let cards = lanes.iter_mut()
+                .flat_map(|lane| lane.cards.items.iter_mut())
+                .chain(archive.iter_mut().map(|entry| &mut entry.card));
+            for card in cards {
+                if card.id.is_empty() || !taken.insert(card.id.clone()) {
+                    card.id = fresh_id(&mut taken);
+                    assigned = true;
+                }
+            }
+        }
+        if assigned { self.dirty = true; }
+    }
+
+    /// Forget the undo history of every board and keep `depth` changes from now on.
+    pub fn set_undo_depth(&mut self, depth: usize) {
+        self.history = History::with_depth(depth);
+        for board in self.boards.iter_mut() {
+            board.history = History::with_depth(depth);
+        }
+    }
+
+    // Put the open board's contents away in its slot of `boards`
+    fn park(&mut self) {
+        let board = &mut self.boards[self.tabs.index];
+        board.lanes = std::mem::take(&mut self.lanes);
+        board.archive = std::mem::take(&mut self.archive);
+        board.filters = std::mem::take(&mut self.filters);
+        board.history = std::mem::replace(&mut self.history, History::with_depth(board.history.depth()));
+        board.current_lane = self.current_lane;
+    }
+
+    // Open the board of the current tab, starting over with no search
+    fn unpark(&mut self) {
+        let depth = self.history.depth();
+        let board = std::mem::replace(&mut self.boards[self.tabs.index], Board {
+            history: History::with_depth(depth),
+            ..Board::default()
+        });
+        self.lanes = if board.lanes.is_empty() {
+            DEFAULT_LANES.iter().map(|name| Lane::new(name)).collect()
+        } else {
+            board.lanes
+        };
+        self.archive = board.archive;
+        self.filters = board.filters;
+        self.history = board.history;
+        self.search.clear();
+        self.archive_filter.clear();
+        self.description_scroll = 0;
+        self.show((board.current_lane, None));
+    }
+
+    pub fn board_name(&self) -> &str {
+        &self.tabs.titles[self.tabs.index]
+    }
+
+    /// Open the board in tab `index`.
+    pub fn switch_board(&mut self, index: usize) {
+        if index == self.tabs.index || index >= self.boards.len() { return; }
+        self.park();
+        self.tabs.index = index;
+        self.unpark();
+    }
+
+    pub fn next_board(&mut self) {
+        if self.boards.len() == 1 { return; }
+        self.park();
+        self.tabs.next();
+        self.unpark();
+    }
+
+    pub fn previous_board(&mut self) {
+        if self.boards.len() == 1 { return; }
+        self.park();
+        self.tabs.previous();
+        self.unpark();
+    }
+
+    // Board names have to tell the tabs apart
+    fn board_name_taken(&self, name: &str) -> bool {
+        self.find_board(name).is_some()
+    }
+
+    /// The tab of the board called `name`, ignoring case.
+    pub fn find_board(&self, name: &str) -> Option<usize> {
+        self.tabs.titles.iter().position(|title| title.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Add a board with the default lanes after the last tab and open it.
+    pub fn add_board(&mut self, name: &str) {
+        if self.read_only() { return; }
+        let name = name.trim();
+        if name.is_empty() { return; }
+        if self.board_name_taken(name) {
+            self.message = Some(format!("There is already a board called '{}'", name));
+            return;
+        }
+        self.park();
+        self.tabs.titles.push(String::from(name));
+        self.boards.push(Board {
+            history: History::with_depth(self.history.depth()),
+            ..Board::default()
+        });
+        self.tabs.index = self.boards.len() - 1;
+        self.unpark();
+        self.dirty = true;
+    }
+
+    pub fn rename_board(&mut self, name: &str) {
+        if self.read_only() { return; }
+        let name = name.trim();
+        if name.is_empty() || name == self.board_name() { return; }
+        if self.board_name_taken(name) && !self.board_name().eq_ignore_ascii_case(name) {
+            self.message = Some(format!("There is already a board called '{}'", name));
+            return;
+        }
+        self.tabs.titles[self.tabs.index] = String::from(name);
+        self.dirty = true;
+    }
+
+    /// Ask before deleting the open board, unless it is the only one.
+    pub fn ask_delete_board(&mut self) {
+        if self.read_only() { return; }
+        if self.boards.len() == 1 {
+            self.message = Some(String::from("Can't delete the last board"));
+            return;
+        }
+        self.input_mode = InputMode::ConfirmDeleteBoard;
+    }
+
+    /// Delete the open board, cards, archive and all, and open the one
+    /// before it. This can't be undone.
+    pub fn delete_board(&mut self) {
+        if self.boards.len() == 1 { return; }
+        let name = self.tabs.titles.remove(self.tabs.index);
+        self.boards.remove(self.tabs.index);
+        self.tabs.index = self.tabs.index.saturating_sub(1);
+        self.unpark();
+        self.message = Some(format!("Deleted the board '{}'", name));
+        self.dirty = true;
+    }
+
+    // Read-only boards can be looked at but not changed; says so if this one is
+    fn read_only(&mut self) -> bool {
+        if self.readonly {
+            self.message = Some(String::from("The board is open read-only"));
+        }
+        self.readonly
+    }
+
+    /// The lane called `name`, ignoring case.
+    pub fn find_lane(&self, name: &str) -> Option<usize> {
+        self.lanes.iter().position(|lane| lane.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The lane finished cards go to: the first one called Done, Finished or Complete(d).
+    pub fn done_lane(&self) -> Option<usize> {
+        ["done", "finished", "complete", "completed"]
+            .iter()
+            .find_map(|name| self.find_lane(name))
+    }
+
+    /// Where the card with ID `id` is on the open board. A leading `#` is fine.
+    pub fn find_card(&self, id: &str) -> Option<(usize, usize)> {
+        let id = id.trim().trim_start_matches('#').to_lowercase();
+        self.lanes.iter().enumerate().find_map(|(lane, cards)| {
+            cards.cards.items.iter().position(|card| card.id == id).map(|index| (lane, index))
+        })
+    }
+
+    /// The tab of the board the card with ID `id` is on, open or not.
+    pub fn board_with_card(&self, id: &str) -> Option<usize> {
+        if self.find_card(id).is_some() { return Some(self.tabs.index); }
+        let id = id.trim().trim_start_matches('#').to_lowercase();
+        self.boards.iter().position(|board| {
+            board.lanes.iter().any(|lane| lane.cards.items.iter().any(|card| card.id == id))
+        })
+    }
+
+    /// Focus `lane` and select card `index` in it.
+    pub fn focus_card(&mut self, lane: usize, index: usize) {
+        self.show((lane, Some(index)));
+    }
+
+    /// Index of the selected card in the current lane, if there is one.
+    pub fn current_index(&self) -> Option<usize> {
+        let lane = &self.lanes[self.current_lane].cards;
+        lane.state.selected().filter(|index| *index < lane.items.len())
+    }
+
+    pub fn current_card(&self) -> Option<&Card> {
+        let index = self.current_index()?;
+        self.lanes[self.current_lane].cards.items.get(index)
+    }
+
+    /// Change the selected card through `update`, which is recorded
+    /// as one change (if anything changed). None without a selection.
+    pub fn update_card<F, R>(&mut self, update: F) -> Option<R>
+        where
+            F: FnOnce(&mut Card) -> R,
+    {
+        let index = self.current_index()?;
+        let before = self.lanes[self.current_lane].cards.items[index].clone();
+        let mut after = before.clone();
+        let result = update(&mut after);
+        if after != before {
+            after.updated = Some(Utc::now());
+            self.perform(Change::UpdateCard {
+                lane: self.current_lane,
+                index,
+                before: Box::new(before),
+                after: Box::new(after),
+            });
+        }
+        Some(result)
+    }
+
+    /// Make a change to the lanes and remember it for undo.
+    pub fn perform(&mut self, change: Change) {
+        if self.read_only() { return; }
+        let mut focus = change.apply(&mut self.lanes, &mut self.archive);
+        // Sorted lanes put any card that was added or changed in its place,
+        // as part of the same change so they are undone together:
+        let mut changes = vec![change];
+        for (lane, cards) in self.lanes.iter().enumerate() {
+            if cards.sort == SortMode::Manual { continue; }
+            let order = cards.sorted_order(cards.sort);
+            if order.iter().enumerate().all(|(index, old)| index == *old) { continue; }
+            changes.push(Change::sort_lane(lane, cards, cards.sort));
+        }
+        for sort in &changes[1..] {
+            focus = sort.follow(focus, &self.lanes);
+            sort.apply(&mut self.lanes, &mut self.archive);
+        }
+        let change = if changes.len() == 1 { changes.remove(0) } else { Change::Group(changes) };
+        self.history.record(change);
+        self.show(focus);
+        self.dirty = true;
+    }
+
+    pub fn undo(&mut self) {
+        if self.read_only() { return; }
+        match self.history.undo() {
+            Some(change) => {
+                let focus = change.apply(&mut self.lanes, &mut self.archive);
+                self.show(focus);
+                self.dirty = true;
+            },
+            None => { self.message = Some(String::from("Nothing to undo")); },
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.read_only() { return; }
+        match self.history.redo() {
+            Some(change) => {
+                let focus = change.apply(&mut self.lanes, &mut self.archive);
+                self.show(focus);
+                self.dirty = true;
+            },
+            None => { self.message = Some(String::from("Nothing to redo")); },
+        }
+    }
+
+    // Focus the lane a change happened in, selecting the card it changed
+    // (or keeping the lane's own selection when it was a lane that changed):
+    fn show(&mut self, (lane, card): (usize, Option<usize>)) {
+        self.current_lane = lane.min(self.lanes.len() - 1);
+        for (index, other) in self.lanes.iter_mut().enumerate() {
+            if index != self.current_lane { other.cards.unselect(); }
+        }
+        let cards = &mut self.lanes[self.current_lane].cards;
+        let selected = card.or_else(|| cards.state.selected()).unwrap_or(0);
+        if cards.items.is_empty() {
+            cards.unselect();
+        } else {
+            cards.state.select(Some(selected.min(cards.items.len() - 1)));
+        }
+        self.description_scroll = 0;
+    }
+
+    /// What cards are being searched for, lowercased. While the search
+    /// is being typed it is still in the input box.
+    pub fn search_text(&self) -> String {
+        let search = match self.input_mode {
+            InputMode::Search => &self.input,
+            _ => &self.search,
+        };
+        search.trim().to_lowercase()
+    }
+
+    /// The search as a query. One that doesn't parse (yet) hides nothing.
+    pub fn filter(&self) -> Filter {
+        query::parse(&self.search_text(), Local::now().date_naive())
+            .unwrap_or_else(|_| Filter::All(Vec::new()))
+    }
+
+    /// What is wrong with the search, if it doesn't parse.
+    pub fn filter_error(&self) -> Option<String> {
+        query::parse(&self.search_text(), Local::now().date_naive()).err()
+    }
+
+    /// Indices of the cards in `lane` that are shown, which is all of
+    /// them unless there is a search.
+    pub fn visible_cards(&self, lane: usize) -> Vec<usize> {
+        let filter = self.filter();
+        let today = Local::now().date_naive();
+        let lane = &self.lanes[lane];
+        lane.cards.items
+            .iter()
+            .enumerate()
+            .filter(|(_, card)| filter.matches(card, &lane.name, today))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn next_card(&mut self) {
+        self.step_card(true);
+    }
+
+    pub fn previous_card(&mut self) {
+        self.step_card(false);
+    }
+
+    // Select the next (or previous) shown card, wrapping around the lane
+    fn step_card(&mut self, forward: bool) {
+        let visible = self.visible_cards(self.current_lane);
+        if visible.is_empty() { return; }
+        let position = self.current_index().and_then(|index| visible.iter().position(|v| *v == index));
+        let next = match position {
+            Some(position) if forward => (position + 1) % visible.len(),
+            Some(position) => (position + visible.len() - 1) % visible.len(),
+            None => 0,
+        };
+        self.lanes[self.current_lane].cards.state.select(Some(visible[next]));
+        self.description_scroll = 0;
+    }
+
+    /// Select shown card `index` of the current lane, or the last one if it is past the end.
+    pub fn select_card(&mut self, index: usize) {
+        let visible = self.visible_cards(self.current_lane);
+        if visible.is_empty() { return; }
+        let selected = visible[index.min(visible.len() - 1)];
+        self.lanes[self.current_lane].cards.state.select(Some(selected));
+        self.description_scroll = 0;
+    }
+
+    /// Filter the lanes down to the cards matching `search` (all of them
+    /// when it is empty), moving to the first hit if the selection is hidden.
+    pub fn set_search(&mut self, search: &str) {
+        self.search = String::from(search.trim());
+        self.follow_search();
+    }
+
+    /// Keep the selection on a shown card while a search is typed or set.
+    pub fn follow_search(&mut self) {
+        let visible = self.visible_cards(self.current_lane);
+        if self.current_index().is_some_and(|index| visible.contains(&index)) { return; }
+        if !self.jump_to_hit(true) {
+            self.lanes[self.current_lane].cards.unselect();
+        }
+    }
+
+    /// Go to the next (or previous) card that matches the search, in any lane.
+    /// Returns false if there are none.
+    pub fn jump_to_hit(&mut self, forward: bool) -> bool {
+        let hits: Vec<(usize, usize)> = (0..self.lanes.len())
+            .flat_map(|lane| self.visible_cards(lane).into_iter().map(move |index| (lane, index)))
+            .collect();
+        if hits.is_empty() { return false; }
+        let here = (self.current_lane, self.current_index());
+        let hit = if forward {
+            hits.iter().find(|(lane, index)| (*lane, Some(*index)) > here).unwrap_or(&hits[0])
+        } else {
+            hits.iter().rev().find(|(lane, index)| (*lane, Some(*index)) < here).unwrap_or(&hits[hits.len() - 1])
+        };
+        let (lane, index) = *hit;
+        for other in self.lanes.iter_mut() {
+            other.cards.unselect();
+        }
+        self.current_lane = lane;
+        self.lanes[lane].cards.state.select(Some(index));
+        self.description_scroll = 0;
+        true
+    }
+
+    /// Jump between hits with n/N; says so when there is nothing to jump to.
+    pub fn next_hit(&mut self, forward: bool) {
+        if self.search.is_empty() {
+            self.message = Some(String::from("Nothing is being searched for"));
+        } else if !self.jump_to_hit(forward) {
+            self.message = Some(format!("No cards match '{}'", self.search));
+        }
+    }
+
+    /// Keep the current search as `name`, replacing a filter of the same name.
+    pub fn save_filter(&mut self, name: &str) {
+        if self.read_only() { return; }
+        let name = name.trim();
+        if name.is_empty() || self.search.is_empty() { return; }
+        let filter = SavedFilter { name: String::from(name), query: self.search.clone() };
+        match self.filters.iter_mut().find(|saved| saved.name == name) {
+            Some(saved) => { *saved = filter; },
+            None => { self.filters.push(filter); },
+        }
+        self.message = Some(format!("Saved the filter '{}'", name));
+        self.dirty = true;
+    }
+
+    pub fn open_filters(&mut self) {
+        if self.filters.is_empty() {
+            self.message = Some(String::from("No filters have been saved yet"));
+            return;
+        }
+        self.input_mode = InputMode::Filters;
+        self.filter_list.select(Some(0));
+    }
+
+    pub fn next_filter(&mut self) {
+        let selected = self.filter_list.selected().unwrap_or(0);
+        if selected + 1 < self.filters.len() { self.filter_list.select(Some(selected + 1)); }
+    }
+
+    pub fn previous_filter(&mut self) {
+        let selected = self.filter_list.selected().unwrap_or(0);
+        if selected > 0 { self.filter_list.select(Some(selected - 1)); }
+    }
+
+    /// Search with the selected filter and go back to the lanes.
+    pub fn apply_filter(&mut self) {
+        let query = match self.filter_list.selected().and_then(|selected| self.filters.get(selected)) {
+            Some(filter) => filter.query.clone(),
+            None => return,
+        };
+        self.input_mode = InputMode::Normal;
+        self.set_search(&query);
+        if let Some(error) = self.filter_error() {
+            self.message = Some(error);
+        } else if self.current_card().is_none() {
+            self.message = Some(format!("No cards match '{}'", self.search));
+        }
+    }
+
+    pub fn delete_filter(&mut self) {
+        if self.read_only() { return; }
+        let selected = match self.filter_list.selected() {
+            Some(selected) if selected < self.filters.len() => selected,
+            _ => return,
+        };
+        let filter = self.filters.remove(selected);
+        self.message = Some(format!("Deleted the filter '{}'", filter.name));
+        self.dirty = true;
+        if self.filters.is_empty() {
+            self.filter_list.select(None);
+            self.input_mode = InputMode::Normal;
+        } else {
+            self.filter_list.select(Some(selected.min(self.filters.len() - 1)));
+        }
+    }
+
+    /// Scroll the description by `rows`; draw_description stops it at the end.
+    pub fn scroll_description(&mut self, rows: isize) {
+        self.description_scroll = (self.description_scroll as isize + rows).max(0) as usize;
+    }
+
+    /// Append a new card to the current lane and select it.
+    pub fn add_card(&mut self, title: &str) {
+        let index = self.lanes[self.current_lane].cards.items.len();
+        let card = Card { id: self.new_id(), ..Card::new(title) };
+        self.perform(Change::InsertCard { lane: self.current_lane, index, card });
+    }
+
+    /// Append a line to the selected card's description.
+    /// Returns false if there is no card selected.
+    pub fn add_description_line(&mut self, line: &str) -> bool {
+        self.update_card(|card| card.description.push(String::from(line))).is_some()
+    }
+
+    /// Set one SMART criterion on the selected card. An answer to
+    /// Time-bound that reads as a date also becomes the due date.
+    pub fn set_smart(&mut self, criterion: Criterion, value: &str) -> bool {
+        self.update_card(|card| {
+            *card.smart.get_mut(criterion) = String::from(value.trim());
+            if criterion == Criterion::TimeBound {
+                if let Some(due) = date::parse_date(value, Local::now().date_naive()) {
+                    card.due = Some(due);
+                }
+            }
+        }).is_some()
+    }
+
+    /// The keys that the InputMode reads from the keymap.
+    pub fn keymap_mode(&self) -> Mode {
+        match self.input_mode {
+            InputMode::Normal => Mode::Normal,
+            InputMode::Checklist => Mode::Checklist,
+            InputMode::Edit => Mode::Edit,
+            InputMode::Help => Mode::Help,
+            InputMode::ConfirmDelete(_) |
+            InputMode::ConfirmDeleteBoard => Mode::Confirm,
+            InputMode::Archive => Mode::Archive,
+            InputMode::Filters => Mode::Filters,
+            _ => Mode::Typing,
+        }
+    }
+
+    /// Type `key` into the input box, if it's something that can be typed.
+    pub fn type_key(&mut self, key: Key) {
+        match (self.keymap.lookup(Mode::Typing, &[key]), key) {
+            (Lookup::Action(Action::DeleteChar), _) => { self.input.pop(); },
+            (Lookup::None, Key::Char(c)) => { self.input.push(c); },
+            _ => {},
+        }
+    }
+
+    /// Every tag on the board or in the archive, sorted.
+    pub fn all_tags(&self) -> Vec<String> {
+        let cards = self.lanes.iter()
+            .flat_map(|lane| lane.cards.items.iter())
+            .chain(self.archive.iter().map(|entry| &entry.card));
+        let mut tags: Vec<String> = cards.flat_map(|card| card.tags.iter().cloned()).collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// The existing tags that the last word in the input box could be.
+    pub fn tag_completions(&self) -> Vec<String> {
+        let typed = parse_tags(&self.input);
+        let word = match self.input.chars().last() {
+            Some(c) if !c.is_whitespace() && c != ',' => {
+                self.input.rsplit(|c: char| c.is_whitespace() || c == ',').next().unwrap_or("")
+            },
+            _ => return Vec::new(),
+        };
+        let word = word.trim_start_matches('#').to_lowercase();
+        self.all_tags()
+            .into_iter()
+            .filter(|tag| tag.starts_with(&word) && (*tag == word || !typed.contains(tag)))
+            .collect()
+    }
+
+    /// Complete the tag being typed as far as the existing tags agree,
+    /// and start the next one if there is only one it could be.
+    pub fn complete_tag(&mut self) {
+        let completions = self.tag_completions();
+        let first = match completions.first() {
+            Some(first) => first,
+            None => return,
+        };
+        // In bytes, but only ever at the end of a char both tags share:
+        let common = completions.iter().fold(first.len(), |common, tag| {
+            let shared = first.char_indices()
+                .zip(tag.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(first.len().min(tag.len()), |((at, _), _)| at);
+            common.min(shared)
+        });
+        // After the last separator, which can be more than one byte:
+        let start = self.input
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| c.is_whitespace() || c == ',')
+            .map_or(0, |(at, separator)| at + separator.len_utf8());
+        self.input.truncate(start);
+        self.input.push_str(&first[..common]);
+        if completions.len() == 1 {
+            self.input.push(' ');
+        }
+    }
+
+    pub fn open_help(&mut self) {
+        self.input_mode = InputMode::Help;
+        self.help_scroll = 0;
+    }
+
+    /// Scroll the help by `rows`; draw_help stops it at the end.
+    pub fn scroll_help(&mut self, rows: isize) {
+        self.help_scroll = (self.help_scroll as isize + rows).max(0) as usize;
+    }
+
+    /// Move into the selected card's description to work through its checklist.
+    pub fn open_checklist(&mut self) {
+        match self.current_card() {
+            Some(card) if !card.description.is_empty() => {
+                self.checklist.select(Some(0));
+                self.input_mode = InputMode::Checklist;
+            },
+            Some(_) => { self.message = Some(String::from("This card has no description lines")); },
+            None => {},
+        }
+    }
+
+    pub fn next_line(&mut self) {
+        let lines = self.current_card().map_or(0, |card| card.description.len());
+        let line = self.checklist.selected().unwrap_or(0);
+        if line + 1 < lines { self.checklist.select(Some(line + 1)); }
+    }
+
+    pub fn previous_line(&mut self) {
+        let line = self.checklist.selected().unwrap_or(0);
+        if line > 0 { self.checklist.select(Some(line - 1)); }
+    }
+
+    /// Tick or untick the selected line. A plain line becomes an unticked box.
+    pub fn toggle_line(&mut self) {
+        self.update_line(|done| match done {
+            Some(done) => Some(!done),
+            None => Some(false),
+        });
+    }
+
+    /// Add a checkbox to the selected line, or take it away again.
+    pub fn toggle_line_checkbox(&mut self) {
+        self.update_line(|done| match done {
+            Some(_) => None,
+            None => Some(false),
+        });
+    }
+
+    fn update_line<F>(&mut self, change: F)
+        where
+            F: Fn(Option<bool>) -> Option<bool>,
+    {
+        let line = match self.checklist.selected() {
+            Some(line) => line,
+            None => return,
+        };
+        self.update_card(|card| {
+            if let Some(current) = card.description.get(line) {
+                // Nested items keep their indentation
+                let indent = &current[..current.len() - current.trim_start().len()];
+                let (done, text) = split_checkbox(current);
+                let changed = format!("{}{}", indent, with_checkbox(text, change(done)));
+                card.description[line] = changed;
+            }
+        });
+    }
+
+    /// Step the selected card's numeric progress up or down by `step`.
+    pub fn step_progress(&mut self, step: i64) {
+        if self.current_card().and_then(|card| card.progress.as_ref()).is_none() {
+            self.message = Some(String::from("Give the card a progress target in the editor first"));
+            return;
+        }
+        self.update_card(|card| if let Some(progress) = card.progress.as_mut() {
+            progress.current = (progress.current as i64 + step).max(0) as u32;
+        });
+    }
+
+    /// Move the selected card to the end of the lane to the left (-1)
+    /// or right (1), and follow it there.
+    pub fn move_card(&mut self, offset: isize) {
+        let target = self.current_lane as isize + offset;
+        if target < 0 || target as usize >= self.lanes.len() { return; }
+        let target = target as usize;
+        if let (Some(index), Some(card)) = (self.current_index(), self.current_card()) {
+            let mut moved = card.clone();
+            moved.updated = Some(Utc::now());
+            moved.entered_lane = moved.updated;
+            let end = self.lanes[target].cards.items.len();
+            self.perform(Change::Group(vec![
+                Change::RemoveCard { lane: self.current_lane, index, card: card.clone() },
+                Change::InsertCard { lane: target, index: end, card: moved },
+            ]));
+        }
+    }
+
+    /// Move the selected card up (-1) or down (1) within its lane.
+    /// Sorted lanes decide the order themselves.
+    pub fn shift_card(&mut self, offset: isize) {
+        let lane = &self.lanes[self.current_lane];
+        if lane.sort != SortMode::Manual {
+            self.message = Some(format!(
+                "'{}' is sorted {}, sort it by hand to move cards around", lane.name, lane.sort.name()
+            ));
+            return;
+        }
+        if let Some(index) = self.current_index() {
+            // Past the next shown card, so hidden ones don't swallow the move
+            let visible = self.visible_cards(self.current_lane);
+            let target = match visible.iter().position(|v| *v == index) {
+                Some(position) => position as isize + offset,
+                None => return,
+            };
+            if target < 0 || target as usize >= visible.len() { return; }
+            let id = lane.cards.items[index].id.clone();
+            self.perform(Change::ShiftCard {
+                lane: self.current_lane,
+                id,
+                from: index,
+                to: visible[target as usize],
+            });
+        }
+    }
+
+    /// Raise (1) or lower (-1) the selected card's priority a step.
+    pub fn step_priority(&mut self, step: i8) {
+        self.update_card(|card| {
+            card.priority = if step > 0 { card.priority.raise() } else { card.priority.lower() };
+        });
+    }
+
+    /// Switch the current lane to the next sort mode and sort it.
+    pub fn cycle_sort(&mut self) {
+        let lane = &self.lanes[self.current_lane];
+        let after = lane.sort.next();
+        let sort = Change::sort_lane(self.current_lane, lane, after);
+        self.message = Some(format!("Sorting '{}' {}", lane.name, after.name()));
+        self.perform(sort);
+    }
+
+    /// Ask before deleting `count` cards, starting with the selected one.
+    pub fn ask_delete(&mut self, count: usize) {
+        if self.current_card().is_some() {
+            self.input_mode = InputMode::ConfirmDelete(count);
+        }
+    }
+
+    /// Remove `count` shown cards from the selected one down, as one change.
+    /// The first of them goes in the register to be pasted.
+    pub fn delete_cards(&mut self, count: usize) {
+        let index = match self.current_index() {
+            Some(index) => index,
+            None => return,
+        };
+        let lane = self.current_lane;
+        let cards = &self.lanes[lane].cards.items;
+        let removed: Vec<Change> = self.visible_cards(lane)
+            .into_iter()
+            .filter(|visible| *visible >= index)
+            .take(count)
+            .enumerate()
+            // Each removal shifts the cards after it up by one
+            .map(|(removed, at)| Change::RemoveCard { lane, index: at - removed, card: cards[at].clone() })
+            .collect();
+        if let Some(Change::RemoveCard { card, .. }) = removed.first() {
+            self.register = Some(card.clone());
+        }
+        self.perform(Change::Group(removed));
+    }
+
+    /// Take the selected card off the board and into the archive.
+    pub fn archive_card(&mut self) {
+        if let (Some(index), Some(card)) = (self.current_index(), self.current_card()) {
+            let entry = ArchivedCard {
+                card: card.clone(),
+                lane: self.lanes[self.current_lane].name.clone(),
+                archived: Utc::now(),
+            };
+            self.message = Some(format!("Archived '{}'", card.title));
+            self.perform(Change::ArchiveCard {
+                lane: self.current_lane,
+                index,
+                archive_index: self.archive.len(),
+                entry,
+            });
+        }
+    }
+
+    pub fn open_archive(&mut self) {
+        self.input_mode = InputMode::Archive;
+        let first = if self.archive_matches().is_empty() { None } else { Some(0) };
+        self.archive_list.select(first);
+    }
+
+    /// Indices into the archive of the cards matching the search, newest first.
+    /// While the search is being typed it is still in the input box.
+    pub fn archive_matches(&self) -> Vec<usize> {
+        let filter = match self.input_mode {
+            InputMode::ArchiveSearch => &self.input,
+            _ => &self.archive_filter,
+        };
+        let filter = filter.trim().to_lowercase();
+        let matches = |entry: &ArchivedCard| {
+            entry.card.title.to_lowercase().contains(&filter)
+                || entry.lane.to_lowercase().contains(&filter)
+                || entry.card.description.iter().any(|line| line.to_lowercase().contains(&filter))
+                || entry.card.tags.iter().any(|tag| tag.contains(&filter))
+        };
+        (0..self.archive.len())
+            .rev()
+            .filter(|index| filter.is_empty() || matches(&self.archive[*index]))
+            .collect()
+    }
+
+    pub fn next_archived(&mut self) {
+        let matches = self.archive_matches().len();
+        let selected = self.archive_list.selected().unwrap_or(0);
+        if selected + 1 < matches { self.archive_list.select(Some(selected + 1)); }
+    }
+
+    pub fn previous_archived(&mut self) {
+        let selected = self.archive_list.selected().unwrap_or(0);
+        if selected > 0 { self.archive_list.select(Some(selected - 1)); }
+    }
+
+    /// Keep the search that was typed and go back to browsing.
+    pub fn search_archive(&mut self) {
+        self.archive_filter = std::mem::take(&mut self.input);
+        self.input_mode = InputMode::Archive;
+        let first = if self.archive_matches().is_empty() { None } else { Some(0) };
+        self.archive_list.select(first);
+    }
+
+    /// Put the selected archived card back at the end of the lane it was
+    /// archived from, or of the current lane if that lane is gone.
+    pub fn restore_card(&mut self) {
+        let matches = self.archive_matches();
+        let archive_index = match self.archive_list.selected().and_then(|selected| matches.get(selected)) {
+            Some(archive_index) => *archive_index,
+            None => return,
+        };
+        let entry = self.archive[archive_index].clone();
+        let lane = self.lanes
+            .iter()
+            .position(|lane| lane.name == entry.lane)
+            .unwrap_or(self.current_lane);
+        let index = self.lanes[lane].cards.items.len();
+        self.message = Some(format!("Restored '{}' to {}", entry.card.title, self.lanes[lane].name));
+        self.perform(Change::RestoreCard { lane, index, archive_index, entry });
+
+        let matches = self.archive_matches().len();
+        let selected = self.archive_list.selected().unwrap_or(0);
+        self.archive_list.select(if matches == 0 { None } else { Some(selected.min(matches - 1)) });
+    }
+
+    /// Put a copy of the selected card in the register.
+    pub fn copy_card(&mut self) {
+        if let Some(card) = self.current_card() {
+            let mut copy = card.clone();
+            // The copy is a new card once it is pasted, with an ID of its own:
+            copy.created = Some(Utc::now());
+            self.register = Some(copy);
+        }
+    }
+
+    /// Insert the register's card below the selected one (or at the
+    /// top of an empty lane) and select it.
+    pub fn paste_card(&mut self) {
+        let mut card = match self.register.clone() {
+            Some(card) => card,
+            None => {
+                self.message = Some(String::from("Nothing to paste, copy or delete a card first"));
+                return;
+            },
+        };
+        card.updated = Some(Utc::now());
+        card.entered_lane = card.updated;
+        // A deleted card keeps its ID when it is put back, a copy gets a new one
+        if card.id.is_empty() || self.card_ids().contains(&card.id) {
+            card.id = self.new_id();
+        }
+        let index = self.current_index().map_or(0, |index| index + 1);
+        self.perform(Change::InsertCard { lane: self.current_lane, index, card });
+    }
+
+    /// Open the selected card in the editor. Does nothing without a selection.
+    pub fn open_editor(&mut self) {
+        if let (Some(index), Some(card)) = (self.current_index(), self.current_card()) {
+            self.editor = Some(CardEditor::new(self.current_lane, index, card.clone()));
+            self.input_mode = InputMode::Edit;
+        }
+    }
+
+    /// Write the edited card back into its lane and return to Normal mode.
+    pub fn close_editor(&mut self) {
+        if let Some(editor) = self.editor.take() {
+            let before = self.lanes[editor.lane].cards.items[editor.index].clone();
+            if before != editor.card {
+                let mut after = editor.card;
+                after.updated = Some(Utc::now());
+                self.perform(Change::UpdateCard {
+                    lane: editor.lane,
+                    index: editor.index,
+                    before: Box::new(before),
+                    after: Box::new(after),
+                });
+            }
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Move the focus to another lane, selecting the first card in it.
+    pub fn focus_lane(&mut self, index: usize) {
+        if index >= self.lanes.len() || index == self.current_lane { return; }
+        self.lanes[self.current_lane].cards.unselect();
+        self.current_lane = index;
+        self.select_card(0);
+        self.description_scroll = 0;
+    }
+
+    pub fn next_lane(&mut self) {
+        self.focus_lane(self.current_lane + 1);
+    }
+
+    pub fn previous_lane(&mut self) {
+        if self.current_lane != 0 {
+            self.focus_lane(self.current_lane - 1);
+        }
+    }
+
+    /// Add an empty lane to the right of the current one and focus it.
+    pub fn add_lane(&mut self, name: &str) {
+        let index = (self.current_lane + 1).min(self.lanes.len());
+        self.perform(Change::InsertLane { index, lane: Lane::new(name) });
+    }
+
+    pub fn rename_lane(&mut self, name: &str) {
+        let before = self.lanes[self.current_lane].name.clone();
+        self.perform(Change::RenameLane { index: self.current_lane, before, after: String::from(name) });
+    }
+
+    /// Only empty lanes can be removed, and there is always at least one lane.
+    pub fn remove_lane(&mut self) {
+        let lane = &self.lanes[self.current_lane];
+        if !lane.cards.items.is_empty() {
+            self.message = Some(format!(
+                "Move the cards out of '{}' before removing it", lane.name
+            ));
+            return;
+        }
+        if self.lanes.len() == 1 {
+            self.message = Some(String::from("Can't remove the last lane"));
+            return;
+        }
+        let lane = lane.clone();
+        self.perform(Change::RemoveLane { index: self.current_lane, lane });
+    }
+
+    /// Swap the current lane with its neighbour to the left (-1) or right (1).
+    pub fn move_lane(&mut self, offset: isize) {
+        let target = self.current_lane as isize + offset;
+        if target < 0 || target as usize >= self.lanes.len() { return; }
+        self.perform(Change::MoveLane { from: self.current_lane, to: target as usize });
+    }
+
+    /// Replace the lanes with `lanes`, read back from an export, as one change
+    /// that can be undone. Cards are matched to the ones on the board by ID and
+    /// keep what the export leaves out (SMART criteria, progress, timestamps);
+    /// the rest are new cards. Lanes keep their sort mode by name.
+    pub fn import_lanes(&mut self, lanes: Vec<Lane>) {
+        if self.read_only() || lanes.is_empty() { return; }
+        let now = Utc::now();
+        let mut existing: HashMap<String, (String, Card)> = self.lanes.iter()
+            .flat_map(|lane| lane.cards.items.iter().map(move |card| (lane.name.clone(), card.clone())))
+            .map(|(lane, card)| (card.id.clone(), (lane, card)))
+            .collect();
+        let mut taken = self.card_ids();
+        // Take the old lanes off from the right, so undo puts them back in order:
+        let mut changes: Vec<Change> = self.lanes.iter()
+            .enumerate()
+            .rev()
+            .map(|(index, lane)| Change::RemoveLane { index, lane: lane.clone() })
+            .collect();
+        let mut cards = 0;
+        for (index, mut lane) in lanes.into_iter().enumerate() {
+            if let Some(old) = self.find_lane(&lane.name) {
+                lane.sort = self.lanes[old].sort;
+            }
+            for card in lane.cards.items.iter_mut() {
+                *card = match existing.remove(&card.id) {
+                    Some((old_lane, old)) => {
+                        let mut merged = Card {
+                            title: std::mem::take(&mut card.title),
+                            description: std::mem::take(&mut card.description),
+                            priority: card.priority,
+                            due: card.due,
+                            tags: std::mem::take(&mut card.tags),
+                            ..old.clone()
+                        };
+                        if merged != old { merged.updated = Some(now); }
+                        if old_lane != lane.name { merged.entered_lane = Some(now); }
+                        merged
+                    },
+                    // Unknown IDs are fine as long as no other card has them
+                    None if card.id.is_empty() || !taken.insert(card.id.clone()) => {
+                        Card { id: fresh_id(&mut taken), ..std::mem::take(card) }
+                    },
+                    None => std::mem::take(card),
+                };
+                cards += 1;
+            }
+            changes.push(Change::InsertLane { index, lane });
+        }
+        let lanes = changes.len() - self.lanes.len();
+        self.perform(Change::Group(changes));
+        self.focus_lane(0);
+        self.message = Some(format!("Imported {} cards in {} lanes", cards, lanes));
+    }
+}
+
+/// What is on one board. Only the boards that aren't open are kept like
+/// this, see App::boards.
+#[derive(Clone, Default)]
+pub struct Board {
+    pub lanes: Vec<Lane>,
+    pub archive: Vec<ArchivedCard>,
+    pub filters: Vec<SavedFilter>,
+    // Each board has its own undo, as changes are made to its lanes:
+    pub history: History,
+    pub current_lane: usize,
+}
+
+#[derive(Clone)]
+pub struct Lane {
+    pub name: String,
+    pub cards: StatefulList<Card>,
+    // Anything but Manual keeps the cards in that order:
+    pub sort: SortMode,
+}
+impl Lane {
+    pub fn new(name: &str) -> Lane {
+        Lane::with_cards(name, Vec::new())
+    }
+
+    pub fn with_cards(name: &str, cards: Vec<Card>) -> Lane {
+        Lane {
+            name: String::from(name),
+            cards: StatefulList::with_items(cards),
+            sort: SortMode::Manual,
+        }
+    }
+
+    /// The order the cards should be in under `sort`, as indices into the
+    /// current order. Equal cards keep their current order.
+    pub fn sorted_order(&self, sort: SortMode) -> Vec<usize> {
+        let cards = &self.cards.items;
+        let mut order: Vec<usize> = (0..cards.len()).collect();
+        match sort {
+            SortMode::Manual => {},
+            // Most important first:
+            SortMode::Priority => order.sort_by_key(|i| std::cmp::Reverse(cards[*i].priority)),
+            // Soonest first, cards without a date last:
+            SortMode::Due => order.sort_by_key(|i| (cards[*i].due.is_none(), cards[*i].due)),
+            // Oldest first, like the order they were added in:
+            SortMode::Created => order.sort_by_key(|i| (cards[*i].created.is_none(), cards[*i].created)),
+            SortMode::Title => order.sort_by_key(|i| cards[*i].title.to_lowercase()),
+        }
+        order
+    }
+}
+
+/// How a lane keeps its cards in order.
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    // However they were added or moved by hand:
+    #[default]
+    Manual,
+    Priority,
+    Due,
+    Created,
+    Title,
+}
+impl SortMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            SortMode::Manual => "by hand",
+            SortMode::Priority => "by priority",
+            SortMode::Due => "by due date",
+            SortMode::Created => "by date created",
+            SortMode::Title => "by title",
+        }
+    }
+
+    /// The mode after this one, for cycling through them all.
+    pub fn next(self) -> SortMode {
+        match self {
+            SortMode::Manual => SortMode::Priority,
+            SortMode::Priority => SortMode::Due,
+            SortMode::Due => SortMode::Created,
+            SortMode::Created => SortMode::Title,
+            SortMode::Title => SortMode::Manual,
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Card {
+    // Short, unique on every board and never changed, see App::new_id:
+    pub id: String,
+    pub title: String,
+    pub description: Vec<String>,
+    pub lane: u8,
+    pub priority: Priority,
+    pub smart: SmartGoal,
+    pub due: Option<NaiveDate>,
+    pub progress: Option<Progress>,
+    // Lowercase, without the `#`, see parse_tags:
+    pub tags: Vec<String>,
+    // Cards saved before these existed have no timestamps
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    // When the card arrived in the lane it is in now
+    pub entered_lane: Option<DateTime<Utc>>,
+}
+impl Card {
+    pub fn new(title: &str) -> Card {
+        let now = Utc::now();
+        Card {
+            title: String::from(title),
+            created: Some(now),
+            updated: Some(now),
+            entered_lane: Some(now),
+            ..Card::default()
+        }
+    }
+
+    /// Whether the title, description or a tag contains `search`, which
+    /// must be lowercase. Everything matches an empty search.
+    pub fn matches(&self, search: &str) -> bool {
+        search.is_empty()
+            || self.title.to_lowercase().contains(search)
+            || self.description.iter().any(|line| line.to_lowercase().contains(search))
+            || self.tags.iter().any(|tag| tag.contains(search))
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own == tag)
+    }
+
+    /// (done, total) over the description lines written as checkboxes,
+    /// `[ ] todo` or `[x] done`. None if there aren't any.
+    pub fn checklist(&self) -> Option<(usize, usize)> {
+        let boxes: Vec<bool> = self.description
+            .iter()
+            .filter_map(|line| checkbox(line))
+            .collect();
+        if boxes.is_empty() { return None; }
+        Some((boxes.iter().filter(|done| **done).count(), boxes.len()))
+    }
+
+    /// How far along the card is, from 0.0 to 1.0. A numeric target wins
+    /// over the checklist; cards with neither have no progress.
+    pub fn progress_ratio(&self) -> Option<f64> {
+        if let Some(progress) = &self.progress {
+            return Some(progress.ratio());
+        }
+        let (done, total) = self.checklist()?;
+        Some(done as f64 / total as f64)
+    }
+}
+
+// A random ID that isn't in `taken`, which it is added to
+fn fresh_id(taken: &mut HashSet<String>) -> String {
+    let mut rng = rand::thread_rng();
+    loop {
+        let id: String = (0..ID_LENGTH)
+            .map(|_| ID_CHARS[rng.gen_range(0, ID_CHARS.len())] as char)
+            .collect();
+        if taken.insert(id.clone()) { return id; }
+    }
+}
+
+/// Tags as typed in the editor, separated by spaces or commas:
+/// `#Work, home` is `work` and `home`. Repeats are dropped.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(|c: char| c.is_whitespace() || c == ',') {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Whether a description line is a ticked (`[x]`) or unticked (`[ ]`) checkbox.
+pub fn checkbox(line: &str) -> Option<bool> {
+    split_checkbox(line).0
+}
+
+/// The checkbox state of a line, and its text without the indentation or the box.
+pub fn split_checkbox(line: &str) -> (Option<bool>, &str) {
+    let trimmed = line.trim_start();
+    if let Some(text) = trimmed.strip_prefix("[ ]") {
+        (Some(false), text.trim_start())
+    } else if let Some(text) = trimmed.strip_prefix("[x]").or_else(|| trimmed.strip_prefix("[X]")) {
+        (Some(true), text.trim_start())
+    } else {
+        (None, trimmed)
+    }
+}
+
+fn with_checkbox(text: &str, done: Option<bool>) -> String {
+    match done {
+        Some(true) => format!("[x] {}", text),
+        Some(false) => format!("[ ] {}", text),
+        None => String::from(text),
+    }
+}
+
+/// A card taken off the board, with the lane it was archived from.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedCard {
+    pub card: Card,
+    pub lane: String,
+    pub archived: DateTime<Utc>,
+}
+
+/// A numeric target for the Measurable part of a goal, e.g. 3/10 chapters.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    pub current: u32,
+    pub target: u32,
+    pub unit: String,
+}
+impl Progress {
+    /// Parses `3/10 chapters` (the unit is optional). The target can't be 0.
+    pub fn parse(input: &str) -> Option<Progress> {
+        let (current, rest) = input.trim().split_once('/')?;
+        let rest = rest.trim_start();
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let target: u32 = rest[..digits].parse().ok()?;
+        if target == 0 { return None; }
+        Some(Progress {
+            current: current.trim().parse().ok()?,
+            target,
+            unit: rest[digits..].trim().to_string(),
+        })
+    }
+
+    pub fn ratio(&self) -> f64 {
+        (self.current as f64 / self.target.max(1) as f64).min(1.0)
+    }
+}
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.current, self.target)?;
+        if !self.unit.is_empty() { write!(f, " {}", self.unit)?; }
+        Ok(())
+    }
+}
+
+/// How much a card matters. Saved as its level, 0 (low) to 3 (urgent).
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum Priority {
+    #[default]
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+impl From<u8> for Priority {
+    // Anything past the top of the scale is as urgent as it gets
+    fn from(level: u8) -> Priority {
+        match level {
+            0 => Priority::Low,
+            1 => Priority::Medium,
+            2 => Priority::High,
+            _ => Priority::Urgent,
+        }
+    }
+}
+impl From<Priority> for u8 {
+    fn from(priority: Priority) -> u8 {
+        priority.level()
+    }
+}
+impl Priority {
+    pub const ALL: [Priority; 4] = [Priority::Low, Priority::Medium, Priority::High, Priority::Urgent];
+
+    pub fn level(self) -> u8 {
+        match self {
+            Priority::Low => 0,
+            Priority::Medium => 1,
+            Priority::High => 2,
+            Priority::Urgent => 3,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    /// Shown in front of the card's title.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Priority::Low => " ",
+            Priority::Medium => "•",
+            Priority::High => "▲",
+            Priority::Urgent => "‼",
+        }
+    }
+
+    /// Reads a level (`2`) or a name or the start of one (`high`, `h`).
+    pub fn parse(input: &str) -> Option<Priority> {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() { return None; }
+        if let Ok(level) = input.parse::<u8>() {
+            return Priority::ALL.get(level as usize).copied();
+        }
+        Priority::ALL.iter().copied().find(|priority| priority.name().starts_with(&input))
+    }
+
+    pub fn raise(self) -> Priority {
+        Priority::from(self.level() + 1)
+    }
+
+    pub fn lower(self) -> Priority {
+        Priority::from(self.level().saturating_sub(1))
+    }
+}
+
+/// The five parts of a SMART goal, in the order they are asked for.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Criterion {
+    Specific,
+    Measurable,
+    Achievable,
+    Relevant,
+    TimeBound,
+}
+impl Criterion {
+    pub const ALL: [Criterion; 5] = [
+        Criterion::Specific,
+        Criterion::Measurable,
+        Criterion::Achievable,
+        Criterion::Relevant,
+        Criterion::TimeBound,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Criterion::Specific => "Specific",
+            Criterion::Measurable => "Measurable",
+            Criterion::Achievable => "Achievable",
+            Criterion::Relevant => "Relevant",
+            Criterion::TimeBound => "Time-bound",
+        }
+    }
+
+    pub fn letter(self) -> char {
+        self.name().chars().next().unwrap()
+    }
+
+    /// The question asked while walking through a new card.
+    pub fn prompt(self) -> &'static str {
+        match self {
+            Criterion::Specific => "What exactly will be done?",
+            Criterion::Measurable => "How will you know it is done?",
+            Criterion::Achievable => "Why is this realistic?",
+            Criterion::Relevant => "Why does this matter?",
+            Criterion::TimeBound => "When will it be done by?",
+        }
+    }
+
+    pub fn next(self) -> Option<Criterion> {
+        let index = Criterion::ALL.iter().position(|c| *c == self)?;
+        Criterion::ALL.get(index + 1).copied()
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartGoal {
+    pub specific: String,
+    pub measurable: String,
+    pub achievable: String,
+    pub relevant: String,
+    pub time_bound: String,
+}
+impl SmartGoal {
+    pub fn get(&self, criterion: Criterion) -> &str {
+        match criterion {
+            Criterion::Specific => &self.specific,
+            Criterion::Measurable => &self.measurable,
+            Criterion::Achievable => &self.achievable,
+            Criterion::Relevant => &self.relevant,
+            Criterion::TimeBound => &self.time_bound,
+        }
+    }
+
+    pub fn get_mut(&mut self, criterion: Criterion) -> &mut String {
+        match criterion {
+            Criterion::Specific => &mut self.specific,
+            Criterion::Measurable => &mut self.measurable,
+            Criterion::Achievable => &mut self.achievable,
+            Criterion::Relevant => &mut self.relevant,
+            Criterion::TimeBound => &mut self.time_bound,
+        }
+    }
+
+    pub fn is_met(&self, criterion: Criterion) -> bool {
+        !self.get(criterion).trim().is_empty()
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum InputMode {
+    Normal,
+    Title,
+    Description,
+    // Walking through the SMART criteria of a new card:
+    Smart(Criterion),
+    NewLane,
+    RenameLane,
+    // Choosing a field of the card in app.editor:
+    Edit,
+    // Typing a new value for that field:
+    EditField,
+    // Moving through the selected card's description lines:
+    Checklist,
+    // The full screen help, opened from Normal mode:
+    Help,
+    // Waiting for a yes or no before deleting this many cards:
+    ConfirmDelete(usize),
+    // Browsing archived cards:
+    Archive,
+    // Typing what to search the archive for:
+    ArchiveSearch,
+    // Typing what to search the lanes for, filtering them as it goes:
+    Search,
+    // Naming the search, to save it as a filter:
+    FilterName,
+    // Picking a saved filter:
+    Filters,
+    // Typing the name of a new board:
+    NewBoard,
+    RenameBoard,
+    // Waiting for a yes or no before deleting the open board:
+    ConfirmDeleteBoard,
+    // Typing the file to export the board to, or import its lanes from:
+    ExportPath,
+    ImportPath,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::markdown;
+
+    fn app_with_card(title: &str) -> App {
+        let mut app = App::default();
+        app.add_card(title);
+        app.dirty = false;
+        app
+    }
+
+    #[test]
+    fn description_lines_land_on_the_stored_card() {
+        let mut app = app_with_card("Write the tests");
+        assert!(app.add_description_line("first"));
+        assert!(app.add_description_line("second"));
+
+        let stored = &app.lanes[0].cards.items[0];
+        assert_eq!(stored.description, vec!["first", "second"]);
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn description_needs_a_selected_card() {
+        let mut app = App::default();
+        assert!(!app.add_description_line("lost"));
+        assert!(!app.dirty);
+    }
+
+    #[test]
+    fn new_cards_are_selected() {
+        let mut app = app_with_card("First card");
+        app.add_card("Second card");
+        assert_eq!(app.current_card().unwrap().title, "Second card");
+    }
+
+    #[test]
+    fn update_card_changes_the_stored_card() {
+        let mut app = app_with_card("Old title");
+        app.update_card(|card| card.title = String::from("New title"));
+        assert_eq!(app.lanes[0].cards.items[0].title, "New title");
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn moved_cards_keep_their_description() {
+        let mut app = app_with_card("Move me");
+        app.add_description_line("still here");
+        app.move_card(1);
+
+        assert!(app.lanes[0].cards.items.is_empty());
+        assert_eq!(app.current_lane, 1);
+        assert_eq!(app.current_card().unwrap().description, vec!["still here"]);
+    }
+
+    #[test]
+    fn closing_the_editor_writes_the_card_back() {
+        let mut app = app_with_card("Edit me");
+        app.open_editor();
+        app.editor.as_mut().unwrap().insert_line(false);
+        app.editor.as_mut().unwrap().set_value("edited line");
+        app.close_editor();
+
+        assert_eq!(app.lanes[0].cards.items[0].description, vec!["edited line"]);
+        assert!(app.editor.is_none());
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn deleted_cards_can_be_pasted_elsewhere() {
+        let mut app = app_with_card("Move me along");
+        app.delete_cards(1);
+        assert!(app.lanes[0].cards.items.is_empty());
+        assert_eq!(app.current_index(), None);
+
+        app.next_lane();
+        app.paste_card();
+        app.paste_card();
+        let titles: Vec<_> = app.lanes[1].cards.items.iter().map(|card| card.title.as_str()).collect();
+        assert_eq!(titles, vec!["Move me along", "Move me along"]);
+        assert_eq!(app.current_index(), Some(1));
+    }
+
+    #[test]
+    fn moves_can_be_undone_and_redone() {
+        let mut app = app_with_card("Move me along");
+        app.move_card(1);
+        assert_eq!(app.current_lane, 1);
+
+        app.undo();
+        assert_eq!(app.current_lane, 0);
+        assert_eq!(app.lanes[0].cards.items.len(), 1);
+        assert!(app.lanes[1].cards.items.is_empty());
+        assert_eq!(app.current_card().unwrap().title, "Move me along");
+
+        app.redo();
+        assert_eq!(app.lanes[1].cards.items.len(), 1);
+        assert!(app.lanes[0].cards.items.is_empty());
+    }
+
+    #[test]
+    fn undo_finds_cards_by_id_wherever_they_are() {
+        let titles = |app: &App| -> Vec<String> {
+            app.lanes[0].cards.items.iter().map(|card| card.title.clone()).collect()
+        };
+        let mut app = app_with_card("First");
+        app.add_card("Second");
+        app.add_card("Third");
+        app.select_card(1);
+        app.step_priority(1);
+        // Reordered behind the history's back, e.g. by the command line
+        app.lanes[0].cards.items.rotate_left(1);
+        app.undo();
+        assert_eq!(titles(&app), vec!["Second", "Third", "First"]);
+        assert!(app.lanes[0].cards.items.iter().all(|card| card.priority == Priority::default()));
+
+        // A sort is undone by ID too, leaving a card added since at the end
+        app.select_card(2);
+        app.step_priority(1);
+        app.cycle_sort();
+        assert_eq!(titles(&app), vec!["First", "Second", "Third"]);
+        app.lanes[0].cards.items.insert(0, Card { id: String::from("zzzz"), ..Card::new("Fourth") });
+        app.undo();
+        assert_eq!(titles(&app), vec!["Second", "Third", "First", "Fourth"]);
+        assert_eq!(app.lanes[0].sort, SortMode::Manual);
+
+        // ...and so is a shift
+        app.select_card(0);
+        app.shift_card(1);
+        app.lanes[0].cards.items.swap(1, 3);
+        app.undo();
+        assert_eq!(titles(&app), vec!["Second", "Third", "Fourth", "First"]);
+    }
+
+    #[test]
+    fn new_changes_forget_the_redo_stack() {
+        let mut app = app_with_card("First card");
+        app.add_card("Second card");
+        app.undo();
+        app.add_card("Third card");
+        app.redo();
+        assert_eq!(app.message.as_deref(), Some("Nothing to redo"));
+        let titles: Vec<_> = app.lanes[0].cards.items.iter().map(|card| card.title.as_str()).collect();
+        assert_eq!(titles, vec!["First card", "Third card"]);
+    }
+
+    #[test]
+    fn history_is_limited_to_its_depth() {
+        let mut app = App {
+            history: History::with_depth(2),
+            ..App::default()
+        };
+        for title in ["card one", "card two", "card three"].iter() {
+            app.add_card(title);
+        }
+        app.undo();
+        app.undo();
+        app.undo();
+        assert_eq!(app.lanes[0].cards.items.len(), 1);
+        assert_eq!(app.message.as_deref(), Some("Nothing to undo"));
+    }
+
+    #[test]
+    fn removed_lanes_come_back_in_place() {
+        let mut app = App::default();
+        app.next_lane();
+        app.remove_lane();
+        assert_eq!(app.lanes.len(), 3);
+        app.undo();
+        let names: Vec<_> = app.lanes.iter().map(|lane| lane.name.as_str()).collect();
+        assert_eq!(names, DEFAULT_LANES.to_vec());
+        assert_eq!(app.current_lane, 1);
+    }
+
+    #[test]
+    fn counted_deletes_are_one_change() {
+        let mut app = app_with_card("card one");
+        app.add_card("card two");
+        app.add_card("card three");
+        app.select_card(0);
+        app.delete_cards(5);
+        assert!(app.lanes[0].cards.items.is_empty());
+        assert_eq!(app.register.as_ref().unwrap().title, "card one");
+
+        app.undo();
+        assert_eq!(app.lanes[0].cards.items.len(), 3);
+    }
+
+    #[test]
+    fn archived_cards_can_be_found_and_restored() {
+        let mut app = app_with_card("Finish the report");
+        app.add_card("Book the flights");
+        app.archive_card();
+        app.select_card(0);
+        app.archive_card();
+        assert!(app.lanes[0].cards.items.is_empty());
+        assert_eq!(app.archive.len(), 2);
+
+        app.open_archive();
+        app.input_mode = InputMode::ArchiveSearch;
+        app.input = String::from("FLIGHT");
+        app.search_archive();
+        assert_eq!(app.archive_matches(), vec![0]);
+
+        app.restore_card();
+        assert_eq!(app.lanes[0].cards.items[0].title, "Book the flights");
+        assert_eq!(app.archive.len(), 1);
+        assert!(app.archive_matches().is_empty());
+
+        app.undo();
+        assert_eq!(app.archive.len(), 2);
+        assert!(app.lanes[0].cards.items.is_empty());
+    }
+
+    #[test]
+    fn cards_move_up_and_down_their_lane() {
+        let mut app = app_with_card("card one");
+        app.add_card("card two");
+        app.shift_card(-1);
+        assert_eq!(app.lanes[0].cards.items[0].title, "card two");
+        assert_eq!(app.current_index(), Some(0));
+        app.shift_card(-1);
+        assert_eq!(app.lanes[0].cards.items[0].title, "card two");
+    }
+
+    #[test]
+    fn sorted_lanes_stay_sorted() {
+        let mut app = app_with_card("Bravo card");
+        app.add_card("Charlie card");
+        app.cycle_sort();
+        app.cycle_sort();
+        app.cycle_sort();
+        app.cycle_sort();
+        assert_eq!(app.lanes[0].sort, SortMode::Title);
+
+        // A new card lands in its place and stays selected
+        app.add_card("Alpha card");
+        let titles: Vec<_> = app.lanes[0].cards.items.iter().map(|card| card.title.as_str()).collect();
+        assert_eq!(titles, vec!["Alpha card", "Bravo card", "Charlie card"]);
+        assert_eq!(app.current_card().unwrap().title, "Alpha card");
+
+        app.shift_card(1);
+        assert!(app.message.is_some());
+
+        // Undoing the new card undoes its sort too
+        app.undo();
+        let titles: Vec<_> = app.lanes[0].cards.items.iter().map(|card| card.title.as_str()).collect();
+        assert_eq!(titles, vec!["Bravo card", "Charlie card"]);
+    }
+
+    #[test]
+    fn sorting_by_priority_puts_the_highest_first() {
+        let mut app = app_with_card("low priority");
+        app.add_card("high priority");
+        app.update_card(|card| card.priority = Priority::Urgent);
+        app.cycle_sort();
+        assert_eq!(app.lanes[0].cards.items[0].title, "high priority");
+        assert_eq!(app.current_card().unwrap().title, "high priority");
+
+        app.undo();
+        assert_eq!(app.lanes[0].sort, SortMode::Manual);
+        assert_eq!(app.lanes[0].cards.items[0].title, "low priority");
+    }
+
+    #[test]
+    fn priorities_read_names_and_levels() {
+        assert_eq!(Priority::parse("urgent"), Some(Priority::Urgent));
+        assert_eq!(Priority::parse(" H "), Some(Priority::High));
+        assert_eq!(Priority::parse("1"), Some(Priority::Medium));
+        assert_eq!(Priority::parse("7"), None);
+        assert_eq!(Priority::parse("soon"), None);
+        assert_eq!(Priority::Urgent.raise(), Priority::Urgent);
+        assert_eq!(Priority::Low.lower(), Priority::Low);
+    }
+
+    #[test]
+    fn priorities_are_saved_as_levels() {
+        let card = Card { priority: Priority::High, ..Card::default() };
+        let json = serde_json::to_value(&card).unwrap();
+        assert_eq!(json["priority"], 2);
+        // Older boards could go past the top of the scale
+        let card: Card = serde_json::from_value(serde_json::json!({ "priority": 9 })).unwrap();
+        assert_eq!(card.priority, Priority::Urgent);
+    }
+
+    #[test]
+    fn searching_hides_other_cards_and_jumps_between_hits() {
+        let mut app = app_with_card("Write the report");
+        app.add_card("Book the flights");
+        app.update_card(|card| card.description.push(String::from("before the report is due")));
+        app.next_lane();
+        app.add_card("Report back to the team");
+
+        app.set_search("REPORT");
+        assert_eq!(app.visible_cards(0), vec![0, 1]);
+        app.focus_lane(0);
+        app.select_card(0);
+        app.next_hit(true);
+        assert_eq!((app.current_lane, app.current_index()), (0, Some(1)));
+        app.next_hit(true);
+        assert_eq!((app.current_lane, app.current_index()), (1, Some(0)));
+        // ...and round again
+        app.next_hit(true);
+        assert_eq!((app.current_lane, app.current_index()), (0, Some(0)));
+        app.next_hit(false);
+        assert_eq!((app.current_lane, app.current_index()), (1, Some(0)));
+
+        app.set_search("flights");
+        assert_eq!(app.visible_cards(1), Vec::<usize>::new());
+        assert_eq!(app.current_card().unwrap().title, "Book the flights");
+        app.set_search("");
+        assert_eq!(app.visible_cards(0).len(), 2);
+        // A query that is still being typed shows every card until it parses
+        app.input_mode = InputMode::Search;
+        app.input = String::from("due:<99999999");
+        assert!(app.filter_error().is_some());
+        assert_eq!(app.visible_cards(0).len(), 2);
+        let selected = (app.current_lane, app.current_index());
+        app.follow_search();
+        assert_eq!((app.current_lane, app.current_index()), selected);
+        assert_eq!((app.visible_cards(0).len(), app.visible_cards(1).len()), (2, 1));
+    }
+
+    #[test]
+    fn card_navigation_skips_hidden_cards() {
+        let mut app = app_with_card("alpha one");
+        app.add_card("beta");
+        app.add_card("alpha two");
+        app.set_search("alpha");
+        app.select_card(0);
+        app.next_card();
+        assert_eq!(app.current_card().unwrap().title, "alpha two");
+        app.next_card();
+        assert_eq!(app.current_card().unwrap().title, "alpha one");
+    }
+
+    #[test]
+    fn progress_reads_a_target_and_unit() {
+        let chapters = Progress { current: 3, target: 10, unit: String::from("chapters") };
+        assert!(Progress::parse("3/10 chapters") == Some(chapters.clone()));
+        assert!(Progress::parse(" 3 / 10chapters ") == Some(chapters));
+        assert_eq!(Progress::parse("3/10").unwrap().to_string(), "3/10");
+        assert!(Progress::parse("3/0 chapters").is_none());
+        assert!(Progress::parse("-1/10").is_none());
+        assert!(Progress::parse("three/10").is_none());
+        assert!(Progress::parse("3 chapters").is_none());
+    }
+
+    #[test]
+    fn progress_steps_stop_at_zero() {
+        let mut app = app_with_card("Write the book");
+        app.step_progress(1);
+        assert!(app.message.is_some());
+        app.update_card(|card| card.progress = Progress::parse("1/10 chapters"));
+        app.step_progress(2);
+        assert_eq!(app.current_card().unwrap().progress.as_ref().unwrap().current, 3);
+        app.step_progress(-5);
+        assert_eq!(app.current_card().unwrap().progress.as_ref().unwrap().current, 0);
+        assert_eq!(app.current_card().unwrap().progress_ratio(), Some(0.0));
+    }
+
+    #[test]
+    fn checkboxes_are_read_from_the_description() {
+        assert_eq!(split_checkbox("[ ] book flights"), (Some(false), "book flights"));
+        assert_eq!(split_checkbox("  [X] pack"), (Some(true), "pack"));
+        assert_eq!(split_checkbox("[x]unpack"), (Some(true), "unpack"));
+        assert_eq!(split_checkbox("  just a note"), (None, "just a note"));
+        assert_eq!(split_checkbox("[] not a box"), (None, "[] not a box"));
+
+        let mut card = Card::new("Trip");
+        assert!(card.checklist().is_none());
+        card.description = vec![
+            String::from("Before leaving:"),
+            String::from("[x] book flights"),
+            String::from("  [ ] pack"),
+            String::from("  [X] tickets"),
+        ];
+        assert_eq!(card.checklist(), Some((2, 3)));
+    }
+
+    #[test]
+    fn toggling_lines_keeps_their_indentation() {
+        let mut app = app_with_card("Trip");
+        app.update_card(|card| card.description = vec![
+            String::from("[x] book flights"),
+            String::from("  [ ] pack"),
+            String::from("    passport"),
+        ]);
+        let lines = |app: &App| app.current_card().unwrap().description.clone();
+        app.open_checklist();
+        app.toggle_line();
+        app.next_line();
+        app.toggle_line();
+        app.next_line();
+        // A plain line gets an unticked box
+        app.toggle_line();
+        assert_eq!(lines(&app), ["[ ] book flights", "  [x] pack", "    [ ] passport"]);
+        app.toggle_line_checkbox();
+        app.previous_line();
+        app.toggle_line_checkbox();
+        assert_eq!(lines(&app), ["[ ] book flights", "  pack", "    passport"]);
+    }
+
+    #[test]
+    fn moves_and_deletes_skip_hidden_cards() {
+        let mut app = app_with_card("alpha one");
+        app.add_card("beta");
+        app.add_card("alpha two");
+        let titles = |app: &App| -> Vec<String> {
+            app.lanes[0].cards.items.iter().map(|card| card.title.clone()).collect()
+        };
+        app.set_search("alpha");
+        app.select_card(0);
+        app.shift_card(1);
+        assert_eq!(titles(&app), ["beta", "alpha two", "alpha one"]);
+        assert_eq!(app.current_card().unwrap().title, "alpha one");
+        app.undo();
+        assert_eq!(titles(&app), ["alpha one", "beta", "alpha two"]);
+
+        app.select_card(0);
+        app.delete_cards(2);
+        assert_eq!(titles(&app), ["beta"]);
+        assert_eq!(app.register.as_ref().unwrap().title, "alpha one");
+        app.undo();
+        assert_eq!(titles(&app), ["alpha one", "beta", "alpha two"]);
+    }
+
+    #[test]
+    fn queries_filter_the_lanes_and_can_be_saved() {
+        let mut app = app_with_card("Write the report");
+        app.add_card("Book the flights");
+        for _ in 0..2 { app.step_priority(1); }
+        app.next_lane();
+        app.add_card("Pack");
+        for _ in 0..3 { app.step_priority(1); }
+
+        app.set_search("priority:>=high -lane:todo");
+        assert_eq!(app.visible_cards(0), Vec::<usize>::new());
+        assert_eq!(app.visible_cards(1), vec![0]);
+        assert_eq!(app.current_card().unwrap().title, "Pack");
+
+        // Half typed queries hide nothing until they parse
+        app.set_search("priority:>=");
+        assert!(app.filter_error().is_some());
+        assert_eq!(app.visible_cards(0).len(), 2);
+
+        app.set_search("priority:high");
+        app.save_filter("important");
+        app.set_search("priority:>=high");
+        app.save_filter("important");
+        app.set_search("");
+        assert_eq!(app.filters.len(), 1);
+        app.open_filters();
+        app.apply_filter();
+        assert_eq!(app.search, "priority:>=high");
+        assert_eq!(app.visible_cards(0), vec![1]);
+    }
+
+    #[test]
+    fn tags_are_tidied_and_completed_from_the_board() {
+        assert_eq!(parse_tags("#Work, home  work"), vec!["work", "home"]);
+
+        let mut app = app_with_card("Write the report");
+        app.update_card(|card| card.tags = parse_tags("work writing"));
+        app.add_card("Book the flights");
+        app.update_card(|card| card.tags = parse_tags("travel"));
+        assert_eq!(app.all_tags(), vec!["travel", "work", "writing"]);
+        assert!(app.current_card().unwrap().matches("trav"));
+
+        // Only as far as `work` and `writing` agree...
+        app.input = String::from("home w");
+        app.complete_tag();
+        assert_eq!(app.input, "home w");
+        app.input = String::from("home wo");
+        app.complete_tag();
+        assert_eq!(app.input, "home work ");
+        // ...and not to tags that are already there
+        app.input = String::from("travel t");
+        assert_eq!(app.tag_completions(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn tag_completion_stops_between_chars() {
+        // é and è both start with the byte 0xc3
+        let mut app = app_with_card("Book a table");
+        app.update_card(|card| card.tags = parse_tags("café cafè"));
+        app.input = String::from("ca");
+        app.complete_tag();
+        assert_eq!(app.input, "caf");
+        app.input = String::from("café");
+        app.complete_tag();
+        assert_eq!(app.input, "café ");
+        // An ideographic space is three bytes long
+        app.update_card(|card| card.tags = parse_tags("home work"));
+        app.input = String::from("home\u{3000}wo");
+        app.complete_tag();
+        assert_eq!(app.input, "home\u{3000}work ");
+    }
+
+    #[test]
+    fn reloading_keeps_the_tab_and_settings() {
+        let mut app = app_with_card("Write the report");
+        app.add_board("Team");
+        app.show_ids = true;
+        app.set_undo_depth(5);
+        // What another process saved: the same boards, with a card more on Team
+        let mut other = app.clone();
+        other.add_card("Plan the sprint");
+        other.switch_board(0);
+        let Board { lanes, .. } = other.boards[1].clone();
+        let boards = vec![
+            Board { lanes: other.lanes.clone(), ..Board::default() },
+            Board { lanes, ..Board::default() },
+        ];
+        app.reload(other.tabs.titles.clone(), boards);
+        assert_eq!(app.board_name(), "Team");
+        assert_eq!(app.current_card().unwrap().title, "Plan the sprint");
+        assert!(app.show_ids);
+        assert_eq!(app.history.depth(), 5);
+    }
+
+    #[test]
+    fn each_board_keeps_its_own_lanes_and_undo() {
+        let mut app = app_with_card("Write the report");
+        app.add_board("Team");
+        assert_eq!(app.board_name(), "Team");
+        assert_eq!(app.lanes.len(), DEFAULT_LANES.len());
+        assert!(app.current_card().is_none());
+        app.add_card("Plan the sprint");
+        app.add_board("team");
+        assert_eq!(app.tabs.titles.len(), 2);
+
+        app.switch_board(0);
+        assert_eq!(app.current_card().unwrap().title, "Write the report");
+        // Undo only reaches this board's changes
+        app.undo();
+        assert!(app.current_card().is_none());
+        app.next_board();
+        assert_eq!(app.current_card().unwrap().title, "Plan the sprint");
+
+        app.rename_board("Sprint");
+        app.delete_board();
+        assert_eq!(app.tabs.titles, vec![DEFAULT_BOARD]);
+        assert_eq!(app.boards.len(), 1);
+        app.ask_delete_board();
+        assert!(app.input_mode == InputMode::Normal);
+    }
+
+    #[test]
+    fn cards_keep_their_ids_and_copies_get_new_ones() {
+        let mut app = app_with_card("Write the report");
+        let id = app.current_card().unwrap().id.clone();
+        assert_eq!(id.len(), ID_LENGTH);
+
+        app.copy_card();
+        app.paste_card();
+        let copy = app.current_card().unwrap().id.clone();
+        assert_ne!(copy, id);
+
+        // Deleting and pasting back is a move, so the ID comes along
+        app.select_card(0);
+        app.delete_cards(1);
+        app.paste_card();
+        assert_eq!(app.current_card().unwrap().id, id);
+        app.undo();
+        app.undo();
+        assert_eq!(app.find_card(&id), Some((0, 0)));
+        assert_eq!(app.find_card(&format!("#{}", copy.to_uppercase())), Some((0, 1)));
+
+        // Cards from before there were IDs get one, and so does a repeat
+        app.lanes[0].cards.items[0].id.clear();
+        app.next_lane();
+        app.add_card("Book flights");
+        app.lanes[1].cards.items[0].id = copy.clone();
+        app.assign_ids();
+        let ids = app.card_ids();
+        assert_eq!(ids.len(), 3);
+        assert!(!ids.contains(""));
+    }
+
+    #[test]
+    fn imports_keep_what_the_export_leaves_out() {
+        let mut app = app_with_card("Write the report");
+        app.set_smart(Criterion::Specific, "Ten pages on the budget");
+        app.cycle_sort();
+        let id = app.current_card().unwrap().id.clone();
+
+        // The card is renamed and moved, a lane is gone and a new card is added
+        let text = format!("## Todo\n\n## Finished\n\n- Write the final report `id:{}`\n- Book flights `id:{}`\n", id, id);
+        app.import_lanes(markdown::import(&text).unwrap());
+        assert_eq!(app.lanes.len(), 2);
+        assert_eq!(app.lanes[0].sort, SortMode::Priority);
+        let report = &app.lanes[1].cards.items[0];
+        assert_eq!(report.id, id);
+        assert_eq!(report.title, "Write the final report");
+        assert_eq!(report.smart.specific, "Ten pages on the budget");
+        // The same ID twice is a new card the second time
+        assert_ne!(app.lanes[1].cards.items[1].id, id);
+
+        app.undo();
+        assert_eq!(app.lanes.len(), DEFAULT_LANES.len());
+        assert_eq!(app.lanes[0].cards.items[0].title, "Write the report");
+        app.readonly = true;
+        app.import_lanes(markdown::import(&text).unwrap());
+        assert_eq!(app.lanes.len(), DEFAULT_LANES.len());
+    }
+}
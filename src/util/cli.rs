@@ -0,0 +1,286 @@
+use super::{
+    app::{ App, Priority },
+    date,
+    export::{ self, Format },
+};
+
+use std::fs;
+
+use chrono::Local;
+use clap::{ crate_version, App as Command, AppSettings, Arg, ArgMatches, SubCommand };
+
+/// The command line. Without a subcommand the board opens in the terminal;
+/// the subcommands work on it from scripts and hooks instead.
+pub fn command<'a, 'b>() -> Command<'a, 'b> {
+    Command::new("smart-goal-kanban")
+        .version(crate_version!())
+        .about("A kanban board for SMART goals, in the terminal")
+        .after_help(
+            "Cards are found by ID on every board. add and list work on the board that was \
+            open last unless --tab says which. While the board is open in the terminal, the \
+            app picks up changes made to the file by these commands. If the app changes the \
+            board in the same moment, it reloads the file and drops its own change."
+        )
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(Arg::with_name("board")
+            .long("board")
+            .value_name("FILE")
+            .help("The board file to open, instead of the one in $XDG_DATA_HOME")
+        )
+        .arg(Arg::with_name("config")
+            .long("config")
+            .value_name("FILE")
+            .help("The config file to read, instead of the one in $XDG_CONFIG_HOME")
+        )
+        .arg(Arg::with_name("readonly")
+            .long("readonly")
+            .help("Look at the board without changing or saving it")
+        )
+        .subcommand(SubCommand::with_name("add")
+            .about("Add a card to a board")
+            .arg(Arg::with_name("title").required(true).help("What the card is called"))
+            .arg(tab_arg())
+            .arg(Arg::with_name("lane")
+                .long("lane")
+                .value_name("LANE")
+                .help("The lane to add it to, the first one if not given")
+            )
+            .arg(Arg::with_name("priority")
+                .long("priority")
+                .value_name("PRIORITY")
+                .help("low, medium, high, urgent or 0-3")
+            )
+        )
+        .subcommand(SubCommand::with_name("list")
+            .about("List the cards on a board, with their IDs")
+            .arg(tab_arg())
+            .arg(Arg::with_name("lane")
+                .long("lane")
+                .value_name("LANE")
+                .help("Only list the cards in this lane")
+            )
+        )
+        .subcommand(SubCommand::with_name("move")
+            .about("Move a card to another lane")
+            .arg(Arg::with_name("card").required(true).help("The card's ID, as listed"))
+            .arg(Arg::with_name("lane").required(true).help("The lane to move it to"))
+        )
+        .subcommand(SubCommand::with_name("done")
+            .about("Move a card to the Done (or Finished) lane")
+            .arg(Arg::with_name("card").required(true).help("The card's ID, as listed"))
+        )
+        .subcommand(SubCommand::with_name("export")
+            .about("Write the open board out as Markdown, JSON or CSV")
+            .arg(Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("markdown, json or csv, or what the --output file's extension says")
+            )
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .value_name("FILE")
+                .help("The file to write, instead of stdout")
+            )
+        )
+        .subcommand(SubCommand::with_name("import")
+            .about("Replace the open board's lanes with those in a Markdown export")
+            .arg(Arg::with_name("file").required(true).help("The exported file, edited or not"))
+        )
+}
+
+fn tab_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("tab")
+        .long("tab")
+        .value_name("BOARD")
+        .help("The board to use, instead of the one that was open last")
+}
+
+/// Run subcommand `name` against `app`: the board of the card it is
+/// given, the one --tab names or else the open board. The app is left
+/// on the tab it was on. Whatever it prints goes to stdout; the error
+/// is for stderr.
+pub fn run(app: &mut App, name: &str, args: &ArgMatches) -> Result<(), String> {
+    let changes = name != "list" && name != "export";
+    if changes && app.readonly {
+        return Err(format!("The board is open read-only, '{}' would change it", name));
+    }
+    let open = app.tabs.index;
+    let tab = match (args.value_of("tab"), args.value_of("card")) {
+        (Some(tab), _) => Some(app.find_board(tab).ok_or_else(|| format!(
+            "There is no board called '{}', the boards are {}", tab, app.tabs.titles.join(", ")
+        ))?),
+        (None, Some(id)) => app.board_with_card(id),
+        (None, None) => None,
+    };
+    if let Some(tab) = tab {
+        app.switch_board(tab);
+    }
+    let result = run_on_open_board(app, name, args);
+    app.switch_board(open);
+    result
+}
+
+fn run_on_open_board(app: &mut App, name: &str, args: &ArgMatches) -> Result<(), String> {
+    match name {
+        "add" => {
+            let title = args.value_of("title").unwrap_or_default().trim();
+            if title.is_empty() {
+                return Err(String::from("Cards need a title"));
+            }
+            let lane = match args.value_of("lane") {
+                Some(lane) => find_lane(app, lane)?,
+                None => 0,
+            };
+            let priority = match args.value_of("priority") {
+                Some(priority) => Some(Priority::parse(priority).ok_or_else(|| format!(
+                    "'{}' isn't a priority, use low, medium, high, urgent or 0-3", priority
+                ))?),
+                None => None,
+            };
+            app.focus_lane(lane);
+            app.add_card(title);
+            if let Some(priority) = priority {
+                app.update_card(|card| card.priority = priority);
+            }
+            println!("Added '{}' to {}", title, app.lanes[app.current_lane].name);
+        },
+        "list" => {
+            let only = match args.value_of("lane") {
+                Some(lane) => Some(find_lane(app, lane)?),
+                None => None,
+            };
+            print!("{}", list(app, only));
+        },
+        "move" => {
+            let (lane, index) = find_card(app, args.value_of("card").unwrap_or_default())?;
+            let target = find_lane(app, args.value_of("lane").unwrap_or_default())?;
+            move_card(app, lane, index, target);
+        },
+        "done" => {
+            let (lane, index) = find_card(app, args.value_of("card").unwrap_or_default())?;
+            let target = app.done_lane().ok_or_else(|| String::from(
+                "There is no Done or Finished lane, use move to say which lane it goes to"
+            ))?;
+            move_card(app, lane, index, target);
+        },
+        "export" => {
+            let output = args.value_of("output");
+            let format = match (args.value_of("format"), output) {
+                (Some(name), _) => Format::parse(name)
+                    .ok_or_else(|| format!("'{}' isn't a format to export to, try {}", name, Format::NAMES))?,
+                (None, Some(path)) => Format::from_path(path.as_ref()).unwrap_or(Format::Markdown),
+                (None, None) => Format::Markdown,
+            };
+            let text = export::export(app, format);
+            match output {
+                Some(path) => {
+                    fs::write(path, text).map_err(|err| format!("Could not write {}: {}", path, err))?;
+                },
+                None => { print!("{}", text); },
+            }
+        },
+        "import" => {
+            println!("{}", export::import_file(app, args.value_of("file").unwrap_or_default())?);
+        },
+        _ => return Err(format!("There is no '{}' command", name)),
+    }
+    Ok(())
+}
+
+fn find_lane(app: &App, name: &str) -> Result<usize, String> {
+    app.find_lane(name).ok_or_else(|| {
+        let lanes: Vec<&str> = app.lanes.iter().map(|lane| lane.name.as_str()).collect();
+        format!("There is no lane called '{}', the lanes are {}", name, lanes.join(", "))
+    })
+}
+
+fn find_card(app: &App, id: &str) -> Result<(usize, usize), String> {
+    app.find_card(id).ok_or_else(|| format!("There is no card {} on any board, see list for their IDs", id))
+}
+
+fn move_card(app: &mut App, lane: usize, index: usize, target: usize) {
+    let title = app.lanes[lane].cards.items[index].title.clone();
+    if lane == target {
+        println!("'{}' is already in {}", title, app.lanes[target].name);
+        return;
+    }
+    app.focus_card(lane, index);
+    app.move_card(target as isize - lane as isize);
+    println!("Moved '{}' to {}", title, app.lanes[target].name);
+}
+
+// One lane after another, each card with its ID
+fn list(app: &App, only: Option<usize>) -> String {
+    let today = Local::now().date_naive();
+    let mut out = String::new();
+    for (index, lane) in app.lanes.iter().enumerate() {
+        if only.is_some_and(|only| only != index) { continue; }
+        out.push_str(&format!("{} ({})\n", lane.name, lane.cards.items.len()));
+        for card in &lane.cards.items {
+            out.push_str(&format!("  {} {} {}", card.id, card.priority.glyph(), card.title));
+            for tag in &card.tags {
+                out.push_str(&format!(" #{}", tag));
+            }
+            if let Some(due) = card.due {
+                out.push_str(&format!(" (due {})", date::describe_due(due, today)));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_args(app: &mut App, args: &[&str]) -> Result<(), String> {
+        let args = command().get_matches_from_safe(args).map_err(|err| err.to_string())?;
+        match args.subcommand() {
+            (name, Some(sub_args)) => run(app, name, sub_args),
+            _ => Err(String::from("no subcommand")),
+        }
+    }
+
+    #[test]
+    fn cards_can_be_added_and_moved_by_id() {
+        let mut app = App::default();
+        run_args(&mut app, &["kanban", "add", "Write the report", "--lane", "todo", "--priority", "2"]).unwrap();
+        run_args(&mut app, &["kanban", "add", "Book flights", "--lane", "In Progress"]).unwrap();
+        let report = app.lanes[0].cards.items[0].clone();
+        let flights = app.lanes[1].cards.items[0].id.clone();
+        assert_eq!(report.priority, Priority::High);
+        assert!(list(&app, Some(1)).contains(&format!("  {}   Book flights", flights)));
+
+        run_args(&mut app, &["kanban", "done", &report.id]).unwrap();
+        assert_eq!(app.lanes[2].cards.items[0].title, "Write the report");
+        // IDs stay with the card wherever it goes
+        run_args(&mut app, &["kanban", "move", &format!("#{}", flights), "todo"]).unwrap();
+        assert_eq!(app.find_card(&flights), Some((0, 0)));
+        assert_eq!(app.find_card(&report.id), Some((2, 0)));
+
+        assert!(run_args(&mut app, &["kanban", "move", "nope", "todo"]).is_err());
+        assert!(run_args(&mut app, &["kanban", "add", "x", "--lane", "someday"]).is_err());
+        app.readonly = true;
+        assert!(run_args(&mut app, &["kanban", "done", &report.id]).is_err());
+    }
+
+    #[test]
+    fn cards_are_found_on_every_board() {
+        let mut app = App::default();
+        app.add_board("Team");
+        run_args(&mut app, &["kanban", "add", "Plan the sprint", "--tab", "main"]).unwrap();
+        assert_eq!(app.board_name(), "Team");
+        assert!(app.lanes[0].cards.items.is_empty());
+
+        // The card is on Main, but Team is the open board
+        let id = app.boards[0].lanes[0].cards.items[0].id.clone();
+        run_args(&mut app, &["kanban", "done", &id]).unwrap();
+        assert_eq!(app.board_name(), "Team");
+        app.switch_board(0);
+        assert_eq!(app.find_card(&id), Some((2, 0)));
+        assert!(list(&app, None).contains("Plan the sprint"));
+        assert!(run_args(&mut app, &["kanban", "list", "--tab", "someday"]).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use super::{
+    history,
+    keymap::{ self, Action, Keymap, Mode },
+};
+
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{ Path, PathBuf },
+};
+
+use serde::Deserialize;
+use termion::event::Key;
+
+const APP_DIR: &str = "smart-goal-kanban";
+const CONFIG_FILE: &str = "config.toml";
+
+/// Everything the user can change in the config file. Anything left out
+/// of the file keeps its default.
+///
+/// ```toml
+/// undo_depth = 500
+/// show_ids = true
+///
+/// [keys.normal]
+/// quit = "Q"
+/// next_card = ["Down", "j"]
+/// ```
+#[derive(Clone)]
+pub struct UserConfig {
+    pub keymap: Keymap,
+    // How many changes can be undone:
+    pub undo_depth: usize,
+    // Whether the lanes start out showing card IDs:
+    pub show_ids: bool,
+}
+impl Default for UserConfig {
+    fn default() -> UserConfig {
+        UserConfig {
+            keymap: Keymap::default(),
+            undo_depth: history::DEFAULT_DEPTH,
+            show_ids: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Toml(PathBuf, toml::de::Error),
+    // Every problem found, so they can all be fixed in one go
+    Invalid(PathBuf, Vec<String>),
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => {
+                write!(f, "could not read {}: {}", path.display(), err)
+            },
+            ConfigError::Toml(path, err) => {
+                write!(f, "{} is not valid TOML: {}", path.display(), err)
+            },
+            ConfigError::Invalid(path, problems) => {
+                write!(f, "problems in {}:", path.display())?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+impl Error for ConfigError {}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    undo_depth: Option<usize>,
+    show_ids: bool,
+    // mode -> action -> keys
+    keys: BTreeMap<String, BTreeMap<String, KeyList>>,
+}
+
+// A single key can be written without the brackets
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+impl KeyList {
+    fn names(&self) -> Vec<&str> {
+        match self {
+            KeyList::One(name) => vec![name.as_str()],
+            KeyList::Many(names) => names.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/smart-goal-kanban/config.toml`,
+/// falling back to `~/.config` when XDG_CONFIG_HOME is not set.
+pub fn default_path() -> PathBuf {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var_os("HOME").unwrap_or_default();
+            PathBuf::from(home).join(".config")
+        },
+    };
+    config_home.join(APP_DIR).join(CONFIG_FILE)
+}
+
+impl UserConfig {
+    /// Read and check the config file. A missing file means all defaults.
+    pub fn load(path: &Path) -> Result<UserConfig, ConfigError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(UserConfig::default()),
+            Err(err) => return Err(ConfigError::Io(path.to_path_buf(), err)),
+        };
+        let file: ConfigFile = toml::from_str(&contents)
+            .map_err(|err| ConfigError::Toml(path.to_path_buf(), err))?;
+
+        let mut problems = Vec::new();
+        let keymap = build_keymap(&file, &mut problems);
+        if problems.is_empty() {
+            Ok(UserConfig {
+                keymap,
+                undo_depth: file.undo_depth.unwrap_or(history::DEFAULT_DEPTH),
+                show_ids: file.show_ids,
+            })
+        } else {
+            Err(ConfigError::Invalid(path.to_path_buf(), problems))
+        }
+    }
+}
+
+fn printable(key: &Key) -> bool {
+    matches!(key, Key::Char(c) if *c != '\n' && *c != '\t')
+}
+
+fn build_keymap(file: &ConfigFile, problems: &mut Vec<String>) -> Keymap {
+    let mut keymap = Keymap::default();
+    for (mode_name, actions) in &file.keys {
+        let mode = match Mode::from_name(mode_name) {
+            Some(mode) => mode,
+            None => {
+                let modes: Vec<String> = Mode::ALL.iter().map(|m| m.name().to_lowercase()).collect();
+                problems.push(format!(
+                    "[keys.{}] is not a mode, use one of: {}", mode_name, modes.join(", ")
+                ));
+                continue;
+            },
+        };
+        for (action_name, names) in actions {
+            let action = match Action::from_name(mode, action_name) {
+                Some(action) => action,
+                None => {
+                    problems.push(format!(
+                        "'{}' is not an action in [keys.{}], see the help screen for their names",
+                        action_name, mode_name
+                    ));
+                    continue;
+                },
+            };
+            let mut sequences = Vec::new();
+            for name in names.names() {
+                match keymap::parse_sequence(name) {
+                    // Anything printable has to stay typeable:
+                    Some(keys) if mode == Mode::Typing && keys.iter().any(printable) => {
+                        problems.push(format!(
+                            "'{}' can't be bound to {} in [keys.typing], it could no longer be typed",
+                            name, action_name
+                        ));
+                    },
+                    Some(keys) => sequences.push(keys),
+                    None => problems.push(format!(
+                        "'{}' (for {} in [keys.{}]) is not a key name, try e.g. 'x', 'Enter', 'Ctrl-r' or 'g g'",
+                        name, action_name, mode_name
+                    )),
+                }
+            }
+            keymap.bind(action, &sequences);
+        }
+    }
+
+    for (keys, action, other_keys, other) in keymap.conflicts() {
+        let mode_name = action.mode().name().to_lowercase();
+        if keys == other_keys {
+            problems.push(format!(
+                "'{}' is bound to both {} and {} in [keys.{}]",
+                keymap::sequence_name(keys), action.name(), other.name(), mode_name
+            ));
+        } else {
+            problems.push(format!(
+                "'{}' ({}) is the start of '{}' ({}) in [keys.{}], so only one of them can ever be used",
+                keymap::sequence_name(keys), action.name(),
+                keymap::sequence_name(other_keys), other.name(), mode_name
+            ));
+        }
+    }
+    keymap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::keymap::Lookup;
+
+    fn check(toml: &str) -> (Keymap, Vec<String>) {
+        let file: ConfigFile = toml::from_str(toml).unwrap();
+        let mut problems = Vec::new();
+        let keymap = build_keymap(&file, &mut problems);
+        (keymap, problems)
+    }
+
+    #[test]
+    fn bindings_replace_the_defaults() {
+        let (keymap, problems) = check("[keys.normal]\nquit = [\"Q\", \"Ctrl-c\"]\n");
+        assert!(problems.is_empty());
+        assert_eq!(keymap.keys(Action::Quit), vec![&[Key::Char('Q')], &[Key::Ctrl('c')]]);
+        assert_eq!(keymap.lookup(Mode::Normal, &[Key::Char('q')]), Lookup::None);
+    }
+
+    #[test]
+    fn conflicts_are_reported() {
+        let (_, problems) = check("[keys.normal]\nquit = \"?\"\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("quit"));
+        assert!(problems[0].contains("help"));
+    }
+
+    #[test]
+    fn sequences_can_not_hide_each_other() {
+        let (_, problems) = check("[keys.normal]\nquit = \"y\"\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("start of 'yy'"));
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        let (_, problems) = check(
+            "[keys.nope]\nquit = \"q\"\n[keys.normal]\nfly = \"f\"\nquit = \"Hyper-q\"\n"
+        );
+        assert_eq!(problems.len(), 3);
+    }
+
+    #[test]
+    fn typing_keys_must_stay_typeable() {
+        assert_eq!(check("[keys.typing]\nsubmit = \"s\"\n").1.len(), 1);
+        assert!(check("[keys.typing]\nsubmit = \"Ctrl-s\"\n").1.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{ Datelike, Duration, NaiveDate, Weekday };
+
+/// Cards due within this many days are drawn as due soon.
+pub const DUE_SOON_DAYS: i64 = 2;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DueStatus {
+    Overdue,
+    DueSoon,
+    Later,
+}
+
+pub fn due_status(due: NaiveDate, today: NaiveDate) -> DueStatus {
+    if due < today {
+        DueStatus::Overdue
+    } else if due - today <= Duration::days(DUE_SOON_DAYS) {
+        DueStatus::DueSoon
+    } else {
+        DueStatus::Later
+    }
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+/// A short, rough length of time: `5m`, `3h 20m`, `2d 4h`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+/// How far away a due date is, e.g. `in 3 days`, `today`, `2 days overdue`.
+pub fn describe_due(due: NaiveDate, today: NaiveDate) -> String {
+    match (due - today).num_days() {
+        0 => String::from("today"),
+        1 => String::from("tomorrow"),
+        -1 => String::from("1 day overdue"),
+        days if days < 0 => format!("{} days overdue", -days),
+        days => format!("in {} days", days),
+    }
+}
+
+/// Understands `2026-11-01`, `today`, `tomorrow`, offsets like `+3d` or `+2w`
+/// and weekday names (`fri`, `friday`), which mean the next one from today
+/// (today included). None for anything else, including offsets that go
+/// past the dates chrono can represent.
+pub fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&input, DATE_FORMAT) {
+        return Some(date);
+    }
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.succ_opt(),
+        _ => {},
+    }
+    if let Some(offset) = input.strip_prefix('+') {
+        return today.checked_add_signed(parse_offset(offset)?);
+    }
+    let weekday = parse_weekday(&input)?;
+    let days_ahead = (7 + weekday.num_days_from_monday()
+        - today.weekday().num_days_from_monday()) % 7;
+    today.checked_add_signed(Duration::try_days(days_ahead as i64)?)
+}
+
+// "3d", "2w" or a bare number of days
+fn parse_offset(offset: &str) -> Option<Duration> {
+    let (number, unit) = match offset.chars().last()? {
+        'd' | 'w' => offset.split_at(offset.len() - 1),
+        _ => (offset, "d"),
+    };
+    let number: i64 = number.parse().ok()?;
+    match unit {
+        "w" => Duration::try_weeks(number),
+        _ => Duration::try_days(number),
+    }
+}
+
+fn parse_weekday(input: &str) -> Option<Weekday> {
+    // Accept any prefix of at least three letters: "fri", "frid", "friday"
+    if input.len() < 3 { return None; }
+    let names = [
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ];
+    names.iter()
+        .find(|(name, _)| name.starts_with(input))
+        .map(|(_, weekday)| *weekday)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Sunday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()
+    }
+
+    fn date(month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2026, month, day)
+    }
+
+    #[test]
+    fn dates_parse_relative_to_today() {
+        assert_eq!(parse_date("2026-11-01", today()), date(11, 1));
+        assert_eq!(parse_date(" Today ", today()), date(10, 18));
+        assert_eq!(parse_date("tomorrow", today()), date(10, 19));
+        assert_eq!(parse_date("+3d", today()), date(10, 21));
+        assert_eq!(parse_date("+3", today()), date(10, 21));
+        assert_eq!(parse_date("+2w", today()), date(11, 1));
+    }
+
+    #[test]
+    fn weekdays_mean_the_next_one_from_today() {
+        assert_eq!(parse_date("fri", today()), date(10, 23));
+        assert_eq!(parse_date("Monday", today()), date(10, 19));
+        // Today counts
+        assert_eq!(parse_date("sun", today()), date(10, 18));
+        assert_eq!(parse_date("fr", today()), None);
+    }
+
+    #[test]
+    fn nonsense_and_far_off_dates_are_none() {
+        assert_eq!(parse_date("+99999999d", today()), None);
+        assert_eq!(parse_date("+99999999999999w", today()), None);
+        assert_eq!(parse_date("soon", today()), None);
+        assert_eq!(parse_date("+d", today()), None);
+        assert_eq!(parse_date("2026-13-01", today()), None);
+        assert_eq!(parse_date("", today()), None);
+    }
+}
@@ -1,104 +1,279 @@
-use super::signal::StatefulList;
+use super::{
+    app::{ self, App, Card, Criterion, InputMode, Priority, SortMode },
+    date::{ self, DueStatus },
+    editor::{ CardEditor, EditorField },
+    keymap::{ self, Action, Mode },
+};
+
+use chrono::{ DateTime, Local, Utc };
+use termion::event::Key;
 
 use tui::{
     backend::{ Backend },
-    layout::{ Rect },
+    layout::{ Constraint, Direction, Layout, Rect },
     style::{ Color, Modifier, Style },
     text::{ Span, Spans, Text },
-    widgets::{ Block, Borders, List, ListItem, Paragraph, },
+    widgets::{ Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Table, Tabs, Wrap },
     Frame,
 };
 
-#[derive(Clone)]
-pub struct App {
-    pub input: String,
-    pub input_mode: InputMode,
-    pub lanes: Vec<StatefulList<Card>>,
-    pub current_lane: usize,
-    pub cards: Vec<Card>,
-}
-impl Default for App {
-    fn default() -> App {
-        App {
-            input: String::new(),
-            input_mode: InputMode::Normal,
-            lanes: Vec::new(),
-            current_lane: 0,
-            cards: Vec::new(),
-        }
-    }
+pub fn draw_help_text<B>(f: &mut Frame<B>, chunk: Rect, app: &App)
+    where
+        B: Backend,
+{
+    // Every mode that types into the input box keeps or drops it with these:
+    let (submit, cancel) = (first_key(app, Action::Submit), first_key(app, Action::Cancel));
+    let help_text = match (&app.message, &app.input_mode) {
+        (Some(message), _) => {
+            vec![ Span::styled(message.as_str(), Style::default().fg(Color::Yellow)), ]
+        },
+        // A count or the start of a key sequence, waiting for more:
+        (None, _) if !app.sequence.is_empty() => {
+            vec![ Span::styled(app.sequence.pending(), Style::default().fg(Color::Yellow)), ]
+        },
+        (None, InputMode::Normal) if !app.search.is_empty() => {
+            vec![ Span::raw(format!(
+                "Showing cards matching '{}': '{}'/'{}' next/previous hit, '{}' to save it, '{}' to show all cards",
+                app.search,
+                first_key(app, Action::NextHit),
+                first_key(app, Action::PreviousHit),
+                first_key(app, Action::SaveFilter),
+                first_key(app, Action::ClearSearch)
+            )), ]
+        },
+        // A query that doesn't parse says why as it is typed:
+        (None, InputMode::Search) => match app.filter_error() {
+            Some(error) => vec![ Span::styled(error, Style::default().fg(Color::Red)), ],
+            None => vec![ Span::raw(format!(
+                "Type words or fields (priority:>=high due:<7d lane:todo title:x, '-' to exclude), \
+                 '{}' to keep the filter, '{}' to go back",
+                submit, cancel
+            )), ],
+        },
+        (None, InputMode::FilterName) => {
+            vec![ Span::raw(format!(
+                "Name the filter for '{}', '{}' to save it, '{}' to go back", app.search, submit, cancel
+            )), ]
+        },
+        (None, InputMode::Filters) => {
+            vec![ Span::raw(format!(
+                "'{}' to search with a filter, '{}' to delete it, '{}' to go back",
+                first_key(app, Action::ApplyFilter),
+                first_key(app, Action::DeleteFilter),
+                first_key(app, Action::CloseFilters)
+            )), ]
+        },
+        (None, InputMode::Normal) => {
+            vec![ Span::raw(format!(
+                "Press {} for HELP or '{}' to EXIT",
+                all_keys(app, Action::Help), first_key(app, Action::Quit)
+            )), ]
+        },
+        (None, InputMode::Help) => {
+            vec![ Span::raw(format!("Press '{}' to go back", first_key(app, Action::CloseHelp))), ]
+        },
+        (None, InputMode::Edit) => {
+            vec![ Span::raw(format!(
+                "'{}' to change a field, '{}'/'{}' add a line, '{}' delete a line, \
+                 '{}'/'{}' priority, '{}' to save and close",
+                first_key(app, Action::EditField),
+                first_key(app, Action::InsertLineBelow),
+                first_key(app, Action::InsertLineAbove),
+                first_key(app, Action::DeleteLine),
+                first_key(app, Action::PriorityUp),
+                first_key(app, Action::PriorityDown),
+                first_key(app, Action::CloseEditor)
+            )), ]
+        },
+        (None, InputMode::Smart(criterion)) => {
+            vec![
+                Span::styled(format!("{}: ", criterion.name()), Style::default().fg(Color::Yellow)),
+                Span::raw(criterion.prompt()),
+                Span::raw(format!(" ('{}' to continue, '{}' to stop)", submit, cancel)),
+            ]
+        },
+        (None, InputMode::Checklist) => {
+            vec![ Span::raw(format!(
+                "'{}' to tick a line, '{}' to add/remove its checkbox, '{}' to go back",
+                first_key(app, Action::ToggleItem),
+                first_key(app, Action::ToggleCheckbox),
+                first_key(app, Action::CloseChecklist)
+            )), ]
+        },
+        (None, InputMode::Archive) => {
+            vec![ Span::raw(format!(
+                "'{}' to restore a card, '{}' to search, '{}' to go back",
+                first_key(app, Action::RestoreCard),
+                first_key(app, Action::SearchArchive),
+                first_key(app, Action::CloseArchive)
+            )), ]
+        },
+        (None, InputMode::ArchiveSearch) => {
+            vec![ Span::raw(format!(
+                "Type to search titles, lanes, descriptions and tags, '{}' to keep it, '{}' to go back",
+                submit, cancel
+            )), ]
+        },
+        // The tags the one being typed could be:
+        (None, InputMode::EditField) if app.editor.as_ref().map(CardEditor::field) == Some(EditorField::Tags) => {
+            let completions = app.tag_completions();
+            if completions.is_empty() {
+                vec![ Span::raw(format!(
+                    "Separate tags with spaces, '{}' to keep them, '{}' to discard the change", submit, cancel
+                )), ]
+            } else {
+                let mut spans = vec![ Span::raw(format!("'{}' completes: ", first_key(app, Action::CompleteTag))) ];
+                spans.extend(tag_chips(&completions));
+                spans
+            }
+        },
+        (None, InputMode::EditField) => {
+            vec![ Span::raw(format!("'{}' to keep the change, '{}' to discard it", submit, cancel)), ]
+        },
+        (None, InputMode::ExportPath) => {
+            vec![ Span::raw(format!(
+                "The file to write the board to, as Markdown (.md), JSON (.json) or CSV (.csv), \
+                 '{}' to export, '{}' to go back",
+                submit, cancel
+            )), ]
+        },
+        (None, InputMode::ImportPath) => {
+            vec![ Span::raw(format!(
+                "The Markdown file to replace the lanes with (undo brings them back), '{}' to import, '{}' to go back",
+                submit, cancel
+            )), ]
+        },
+        (None, _) => {
+            vec![ Span::raw(format!("Press '{}' to enter NORMAL mode", cancel)), ]
+        },
+    };
+    let help_message = Text::from(Spans::from(help_text));
+    let help_menu = Paragraph::new(help_message);
+    f.render_widget(help_menu, chunk);
 }
-impl App {
-    pub fn get_current_card(&self) -> Option<Card> {
-        // There should be some check if there are cards
-        // that can be selected or not.
-        // If this is called w/o a card selected - the program panics and crashes
 
-        if self.lanes[self.current_lane].state.selected() == None {
-            return None;
-        }
-        let current_index = self.lanes[self.current_lane]
-            .state.selected().unwrap();
-        let current_card = self.lanes[self.current_lane]
-            .items[current_index].clone();
-        Some(current_card)
-    }
-}
 
-#[derive(Clone, PartialEq)]
-pub struct Card {
-    pub title: String,
-    pub description: Vec<String>,
-    pub lane: u8,
-    pub priority: u8,
-}
-impl Default for Card {
-    fn default() -> Self {
-        Card {
-            title: String::new(),
-            description: Vec::new(),
-            lane: 0,
-            priority: 0,
-        }
+fn first_key(app: &App, action: Action) -> String {
+    match app.keymap.keys(action).first() {
+        Some(keys) => keymap::sequence_name(keys),
+        None => String::from("(unbound)"),
     }
 }
 
-#[derive(Clone)]
-pub enum InputMode {
-    Normal,
-    Title,
-    Description,
+// Every key for `action`, e.g. `'?' or 'F1'`
+fn all_keys(app: &App, action: Action) -> String {
+    let keys: Vec<String> = app.keymap.keys(action)
+        .into_iter()
+        .map(|keys| format!("'{}'", keymap::sequence_name(keys)))
+        .collect();
+    if keys.is_empty() { String::from("(unbound)") } else { keys.join(" or ") }
 }
 
-pub fn draw_help_text<B>(f: &mut Frame<B>, chunk: Rect, app: &App)
+/// Full screen table of every action and the keys bound to it, by mode.
+pub fn draw_help<B>(f: &mut Frame<B>, chunk: Rect, app: &mut App)
     where
         B: Backend,
 {
-    let help_text = match app.input_mode {
-        InputMode::Normal => {
-            vec![ Span::raw("Press 'h' for HELP or 'q' to EXIT"), ]
-        },
-        InputMode::Title |
-        InputMode::Description => {
-            vec![ Span::raw("Press ESC to enter NORMAL mode"), ]
-        },
-    };
-    let help_message = Text::from(Spans::from(help_text));
-    let help_menu = Paragraph::new(help_message);
-    f.render_widget(help_menu, chunk);
+    let mut rows: Vec<[String; 4]> = Vec::new();
+    for mode in Mode::ALL.iter() {
+        if !rows.is_empty() { rows.push(Default::default()); }
+        for (index, (action, keys)) in app.keymap.help(*mode).into_iter().enumerate() {
+            let mode_name = if index == 0 { mode.name() } else { "" };
+            let keys = if keys.is_empty() {
+                String::from("-")
+            } else {
+                keys.into_iter().map(keymap::sequence_name).collect::<Vec<_>>().join(", ")
+            };
+            rows.push([
+                mode_name.to_string(), keys, action.description().to_string(), action.name().to_string()
+            ]);
+        }
+    }
+
+    // Borders, the header and the gap below it take four rows:
+    let height = (chunk.height as usize).saturating_sub(4);
+    app.help_scroll = app.help_scroll.min(rows.len().saturating_sub(height));
+
+    let header_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let visible = rows[app.help_scroll..].iter().map(|row| Row::Data(row.iter()));
+    // The last column is the name to use in config.toml
+    let help = Table::new(["Mode", "Keys", "Action", "Config name"].iter(), visible)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Help ({}-{}/{}){}",
+                app.help_scroll + 1, (app.help_scroll + height).min(rows.len()), rows.len(), moved_keys(app)
+            ))
+        )
+        .header_style(header_style)
+        .widths(&[
+            Constraint::Length(10),
+            Constraint::Length(24),
+            Constraint::Length(40),
+            Constraint::Length(24),
+        ]);
+    f.render_widget(help, chunk);
 }
 
+// The keys that changed with the vim-style bindings, for anyone used to the old
+// ones, unless the config file has bound them back:
+fn moved_keys(app: &App) -> String {
+    let help = keymap::Lookup::Action(Action::Help);
+    let add = keymap::Lookup::Action(Action::AddDescription);
+    let mut moved = Vec::new();
+    if app.keymap.lookup(Mode::Normal, &[Key::Char('h')]) != help {
+        moved.push(format!("help is {} (no longer 'h')", all_keys(app, Action::Help)));
+    }
+    if app.keymap.lookup(Mode::Normal, &[Key::Char('d')]) != add {
+        moved.push(format!("add description is '{}' (no longer 'd')", first_key(app, Action::AddDescription)));
+    }
+    if moved.is_empty() { String::new() } else { format!(" - {}", moved.join(", ")) }
+}
 
 pub fn draw_input_box<B>(f: &mut Frame<B>, chunk: Rect, app: &App)
     where
         B: Backend,
 {
     let title = match app.input_mode {
+        InputMode::Normal if !app.search.is_empty() => { "Search" },
         InputMode::Normal => { "Normal" },
         InputMode::Title => { "Title" },
         InputMode::Description => { "Description" },
+        InputMode::Smart(criterion) => { criterion.name() },
+        InputMode::NewLane => { "New Lane" },
+        InputMode::RenameLane => { "Rename Lane" },
+        InputMode::Edit => { "Edit" },
+        InputMode::Checklist => { "Checklist" },
+        InputMode::Help => { "Help" },
+        InputMode::ConfirmDelete(_) => { "Delete" },
+        InputMode::NewBoard => { "New Board" },
+        InputMode::RenameBoard => { "Rename Board" },
+        InputMode::ConfirmDeleteBoard => { "Delete Board" },
+        InputMode::ExportPath => { "Export To" },
+        InputMode::ImportPath => { "Import From" },
+        InputMode::Archive => { "Archive Search" },
+        InputMode::ArchiveSearch => { "Search Archive" },
+        InputMode::Search => { "Search" },
+        InputMode::FilterName => { "Filter Name" },
+        InputMode::Filters => { "Search" },
+        InputMode::EditField => match app.editor.as_ref().map(CardEditor::field) {
+            Some(EditorField::Title) => { "Edit Title" },
+            Some(EditorField::Priority) => { "Edit Priority (low, medium, high, urgent or 0-3)" },
+            Some(EditorField::Due) => { "Edit Due Date (2026-11-01, +3d, fri)" },
+            Some(EditorField::Progress) => { "Edit Progress (3/10 chapters)" },
+            Some(EditorField::Tags) => { "Edit Tags (separated by spaces)" },
+            Some(EditorField::Smart(criterion)) => { criterion.name() },
+            _ => { "Edit Description" },
+        },
+    };
+    // Browsing the archive shows what it was searched for:
+    let text = match app.input_mode {
+        InputMode::Archive => app.archive_filter.as_str(),
+        InputMode::Normal |
+        InputMode::FilterName |
+        InputMode::Filters => app.search.as_str(),
+        _ => app.input.as_str(),
     };
-    let input_box = Paragraph::new(app.input.as_ref())
+    let input_box = Paragraph::new(text)
         .block(Block::default()
             .title(title)
             .borders(Borders::ALL)
@@ -111,24 +286,68 @@ pub fn draw_lanes<B>(f: &mut Frame<B>, chunk: Vec<Rect>, app: &mut App)
     where
         B: Backend,
 {
-    for index in 0..app.lanes.len() {
-        let current_lane = app.lanes[index].items.clone();
-        let title = match index {
-            0 => { "Todo" },
-            1 => { "In Progress" },
-            2 => { "Finished" },
-            3 => { "In review" },
-            _ => { "How'd you get here?" },
+    let today = Local::now().date_naive();
+    let search = app.search_text();
+    // The words searched for are picked out in the titles:
+    let filter = app.filter();
+    let words = filter.words();
+    for (index, lane_chunk) in chunk.into_iter().enumerate().take(app.lanes.len()) {
+        // Only the cards matching the search are drawn:
+        let visible = app.visible_cards(index);
+        let lane = &app.lanes[index];
+        let mut title = match lane.sort {
+            SortMode::Manual => lane.name.clone(),
+            sort => format!("{} ({})", lane.name, sort.name()),
         };
-        let current_cards: Vec<ListItem> = current_lane
+        if !search.is_empty() {
+            title = format!("{} [{}/{}]", title, visible.len(), lane.cards.items.len());
+        }
+        let current_cards: Vec<ListItem> = visible
             .iter()
+            .map(|card| &lane.cards.items[*card])
             .map(|card|{
-                let li = vec![Spans::from(card.title.as_ref())];
-                ListItem::new(li).style( Style::default())
+                // Priority first, so urgent work stands out:
+                let title_style = match card.priority {
+                    Priority::Urgent => Style::default().add_modifier(Modifier::BOLD),
+                    _ => Style::default(),
+                };
+                let mut spans = vec![
+                    Span::styled(format!("{} ", card.priority.glyph()), priority_style(card.priority)),
+                ];
+                if app.show_ids {
+                    spans.push(Span::styled(format!("{} ", card.id), Style::default().fg(Color::DarkGray)));
+                }
+                spans.extend(highlight(&card.title, &words, title_style));
+                spans.extend(tag_chips(&card.tags));
+                spans.push(Span::raw(" "));
+                if let Some(progress) = &card.progress {
+                    spans.push(Span::raw(format!("{:.0}% ", progress.ratio() * 100.0)));
+                }
+                if let Some((done, total)) = card.checklist() {
+                    spans.push(Span::raw(format!("{}/{} done ", done, total)));
+                }
+                spans.extend(smart_badge(card));
+                // Colour cards that are slipping:
+                let style = match card.due.map(|due| date::due_status(due, today)) {
+                    Some(DueStatus::Overdue) => Style::default().fg(Color::Red),
+                    Some(DueStatus::DueSoon) => Style::default().fg(Color::Yellow),
+                    _ => Style::default(),
+                };
+                if let Some(due) = card.due {
+                    spans.push(Span::raw(format!(" {}", due.format("%b %d"))));
+                }
+                ListItem::new(Spans::from(spans)).style(style)
             })
             .collect();
-        let current_cards = List::new(current_cards.as_ref())
+        // Outline the lane that has focus:
+        let border_style = if index == app.current_lane {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let current_cards = List::new(current_cards)
                 .block(Block::default().borders(Borders::ALL)
+                    .border_style(border_style)
                     .title(title)
                 )
                 .highlight_style(Style::default()
@@ -136,48 +355,535 @@ pub fn draw_lanes<B>(f: &mut Frame<B>, chunk: Vec<Rect>, app: &mut App)
                     .add_modifier(Modifier::BOLD),
                 )
                 .highlight_symbol("> ");
-    f.render_stateful_widget(current_cards, chunk[index], &mut app.lanes[index].state);
+        // The selection is an index into all of the lane's cards,
+        // but only the visible ones are in the list:
+        let mut state = ListState::default();
+        state.select(lane.cards.state.selected().and_then(|selected| {
+            visible.iter().position(|card| *card == selected)
+        }));
+        f.render_stateful_widget(current_cards, lane_chunk, &mut state);
+    }
+}
+
+/// `text` with every hit of the (lowercase) `words` in it picked out.
+fn highlight<'a>(text: &'a str, words: &[&str], style: Style) -> Vec<Span<'a>> {
+    let hit = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let lower = text.to_lowercase();
+    // Lowercasing can change the length of some characters,
+    // in which case the positions are no good and nothing is picked out
+    if words.is_empty() || lower.len() != text.len() {
+        return vec![Span::styled(text, style)];
+    }
+    let mut hits: Vec<(usize, usize)> = words
+        .iter()
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| lower.match_indices(word).map(|(at, found)| (at, at + found.len())))
+        .collect();
+    hits.sort_unstable();
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for (at, end) in hits {
+        if at < start || !text.is_char_boundary(at) || !text.is_char_boundary(end) { continue; }
+        spans.push(Span::styled(&text[start..at], style));
+        spans.push(Span::styled(&text[at..end], hit));
+        start = end;
     }
+    spans.push(Span::styled(&text[start..], style));
+    spans
 }
 
-pub fn draw_description<B>(f: &mut Frame<B>, chunk: Vec<Rect>, app: &App)
+/// The archived cards matching the search, newest first.
+pub fn draw_archive<B>(f: &mut Frame<B>, chunk: Rect, app: &mut App)
     where
         B: Backend,
 {
-//     let text = 
-//     vec![ Spans::from(
-//         vec![ Span::raw("First"),
-//              Span::styled("line",Style::default().add_modifier(Modifier::ITALIC)),
-//              Span::raw("."),
-//              ]),
-//     Spans::from(Span::styled("Second line", Style::default().fg(Color::Red))),
-//     ];
-//
-//
-//     Paragraph::new(text)
-//         .block(Block::default().title("Paragraph").borders(Borders::ALL))
-//         .style(Style::default().fg(Color::White).bg(Color::Black))
-//         .alignment(Alignment::Center)
-//         .wrap(Wrap { trim: true });
-
-    // let current_card = 
-    //     if app.lanes[0].items.len() > 0 { app.get_current_card() }
-    //     else { Card::default() };
-    //     
-    // let current_card = app.get_current_card();
-
-    if let Some(card) = app.get_current_card() {
-        let text = vec![
-            Spans::from( vec![
-                Span::raw(card.description[0].as_str())
-            ]
-        )];
+    let matches = app.archive_matches();
+    let archived_cards = &app.archive;
+    let items: Vec<ListItem> = matches
+        .iter()
+        .map(|index| {
+            let entry = &archived_cards[*index];
+            let archived = entry.archived.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+            ListItem::new(Spans::from(vec![
+                Span::raw(entry.card.title.as_str()),
+                Span::styled(
+                    format!("  from {}, archived {}", entry.lane, archived),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    let title = if matches.len() == app.archive.len() {
+        format!("Archive ({})", app.archive.len())
+    } else {
+        format!("Archive ({} of {})", matches.len(), app.archive.len())
+    };
+    let archive = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    f.render_stateful_widget(archive, chunk, &mut app.archive_list);
+}
+
+/// A small box over the middle of the screen asking before cards are deleted.
+pub fn draw_confirm_delete<B>(f: &mut Frame<B>, chunk: Rect, app: &App, count: usize)
+    where
+        B: Backend,
+{
+    let what = match (count, app.current_card()) {
+        (1, Some(card)) => format!("'{}'", card.title),
+        _ => format!("{} cards", count),
+    };
+    let hint = format!(
+        "'{}' to delete, '{}' to keep it ('{}' undoes it later)",
+        first_key(app, Action::Yes), first_key(app, Action::No), first_key(app, Action::Undo)
+    );
+    draw_confirm(f, chunk, format!("Delete {}?", what), hint);
+}
+
+pub fn draw_confirm_delete_board<B>(f: &mut Frame<B>, chunk: Rect, app: &App)
+    where
+        B: Backend,
+{
+    let cards: usize = app.lanes.iter().map(|lane| lane.cards.items.len()).sum();
+    let question = format!(
+        "Delete the board '{}' with its {} cards and archive?", app.board_name(), cards
+    );
+    let hint = format!(
+        "'{}' to delete, '{}' to keep it (this can't be undone)",
+        first_key(app, Action::Yes), first_key(app, Action::No)
+    );
+    draw_confirm(f, chunk, question, hint);
+}
+
+// A yes or no question in a popup in the middle of `chunk`
+fn draw_confirm<B>(f: &mut Frame<B>, chunk: Rect, question: String, hint: String)
+    where
+        B: Backend,
+{
+    let text = vec![
+        Spans::from(question),
+        Spans::from(""),
+        Spans::from(hint),
+    ];
+    let width = chunk.width.min(60);
+    let height = chunk.height.min(6);
+    let area = Rect::new(
+        chunk.x + (chunk.width - width) / 2,
+        chunk.y + (chunk.height - height) / 2,
+        width,
+        height,
+    );
+    let dialog = Paragraph::new(text)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .title("Delete")
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(dialog, area);
+}
+
+/// One tab per board, the open one picked out.
+pub fn draw_tabs<B>(f: &mut Frame<B>, chunk: Rect, app: &App)
+    where
+        B: Backend,
+{
+    let titles = app.tabs.titles
+        .iter()
+        .enumerate()
+        .map(|(index, title)| Spans::from(format!("{} {}", index + 1, title)))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Boards ('{}'/'{}' to switch){}",
+                first_key(app, Action::PreviousBoard),
+                first_key(app, Action::NextBoard),
+                if app.readonly { " read-only" } else { "" }
+            ))
+        )
+        .select(app.tabs.index)
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    f.render_widget(tabs, chunk);
+}
+
+/// The saved filters, in a popup over the lanes.
+pub fn draw_filters<B>(f: &mut Frame<B>, chunk: Rect, app: &mut App)
+    where
+        B: Backend,
+{
+    let items: Vec<ListItem> = app.filters
+        .iter()
+        .map(|filter| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(filter.name.as_str(), Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(format!("  {}", filter.query), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+    let width = chunk.width.min(70);
+    // One row per filter, and two for the borders:
+    let height = chunk.height.min(items.len() as u16 + 2);
+    let area = Rect::new(
+        chunk.x + (chunk.width - width) / 2,
+        chunk.y + (chunk.height - height) / 2,
+        width,
+        height,
+    );
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(format!("Filters ({})", app.filters.len()))
+        )
+        .highlight_style(Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.filter_list);
+}
+
+/// Each tag as a coloured chip, with a space before it.
+fn tag_chips(tags: &[String]) -> Vec<Span<'static>> {
+    tags.iter()
+        .flat_map(|tag| vec![
+            Span::raw(" "),
+            Span::styled(format!(" {} ", tag), tag_style(tag)),
+        ])
+        .collect()
+}
+
+// The same tag gets the same colour everywhere, and on every run
+fn tag_style(tag: &str) -> Style {
+    const COLOURS: [Color; 6] = [
+        Color::Cyan, Color::Green, Color::Magenta, Color::Blue, Color::Yellow, Color::LightRed,
+    ];
+    let hash = tag.bytes().fold(0usize, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as usize));
+    Style::default().fg(Color::Black).bg(COLOURS[hash % COLOURS.len()])
+}
+
+fn priority_style(priority: Priority) -> Style {
+    match priority {
+        Priority::Low => Style::default().fg(Color::DarkGray),
+        Priority::Medium => Style::default().fg(Color::Blue),
+        Priority::High => Style::default().fg(Color::Magenta),
+        Priority::Urgent => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    }
+}
+
+/// One letter per SMART criterion: green once it is filled in, grey while missing.
+fn smart_badge(card: &Card) -> Vec<Span<'static>> {
+    Criterion::ALL
+        .iter()
+        .map(|criterion| {
+            let style = if card.smart.is_met(*criterion) {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            Span::styled(criterion.letter().to_string(), style)
+        })
+        .collect()
+}
+
+pub fn draw_description<B>(f: &mut Frame<B>, chunk: Vec<Rect>, app: &mut App)
+    where
+        B: Backend,
+{
+    draw_metadata(f, chunk[1], app);
+
+    if let Some(editor) = &app.editor {
+        draw_editor(f, chunk[0], editor);
+        return;
+    }
+    let card = match app.current_card() {
+        Some(card) => card,
+        None => return,
+    };
+
+    // Make room for a progress gauge above the description:
+    let description_chunk = match card.progress_ratio() {
+        Some(ratio) => {
+            let parts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(3),
+                    Constraint::Min(0),
+                ].as_ref())
+                .split(chunk[0]);
+            draw_progress(f, parts[0], card, ratio);
+            parts[1]
+        },
+        None => chunk[0],
+    };
+    let block = Block::default().borders(Borders::ALL);
+
+    if card.description.is_empty() {
+        let placeholder = Paragraph::new(Span::styled(
+            format!(
+                "No description yet. Press '{}' to add lines or '{}' to edit the card.",
+                first_key(app, Action::AddDescription), first_key(app, Action::EditCard)
+            ),
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        ))
+        .block(block);
+        f.render_widget(placeholder, description_chunk);
+        return;
+    }
+
+    // While moving through the checklist the selected line gets a marker,
+    // the same as a List highlight:
+    let selected = match app.input_mode {
+        InputMode::Checklist => app.checklist.selected(),
+        _ => None,
+    };
+    let marker_width = if selected.is_some() { 2 } else { 0 };
+    let width = (description_chunk.width as usize).saturating_sub(2 + marker_width).max(1);
+    let height = (description_chunk.height as usize).saturating_sub(2);
+
+    // Wrap every line ourselves, so we know which rows belong to which line:
+    let mut rows: Vec<Spans> = Vec::new();
+    let mut selected_rows = 0..0;
+    for (index, line) in card.description.iter().enumerate() {
+        let mut style = match app::checkbox(line) {
+            // Ticked items are struck through:
+            Some(true) => Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::CROSSED_OUT),
+            _ => Style::default(),
+        };
+        let is_selected = selected == Some(index);
+        if is_selected {
+            style = style.bg(Color::DarkGray).add_modifier(Modifier::BOLD);
+            selected_rows.start = rows.len();
+        }
+        for (row, text) in wrap(line, width).into_iter().enumerate() {
+            let marker = match (marker_width, is_selected && row == 0) {
+                (0, _) => "",
+                (_, true) => "> ",
+                (_, false) => "  ",
+            };
+            rows.push(Spans::from(vec![
+                Span::raw(marker),
+                Span::styled(text, style),
+            ]));
+        }
+        if is_selected { selected_rows.end = rows.len(); }
+    }
+
+    // Keep the selected line in view, and never scroll past the end:
+    let mut scroll = app.description_scroll;
+    if selected.is_some() {
+        if selected_rows.start < scroll {
+            scroll = selected_rows.start;
+        } else if selected_rows.end > scroll + height {
+            scroll = selected_rows.end.saturating_sub(height);
+        }
+    }
+    scroll = scroll.min(rows.len().saturating_sub(height));
+    app.description_scroll = scroll;
+
+    let block = if rows.len() > height {
+        block.title(format!(
+            "Description ({}-{}/{})",
+            scroll + 1, (scroll + height).min(rows.len()), rows.len()
+        ))
+    } else {
+        block.title("Description")
+    };
+    let description = Paragraph::new(rows)
+        .block(block)
+        .scroll((scroll as u16, 0));
+    f.render_widget(description, description_chunk);
+}
+
+/// Everything about the selected card that isn't its description.
+fn draw_metadata<B>(f: &mut Frame<B>, chunk: Rect, app: &App)
+    where
+        B: Backend,
+{
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Details");
+    let card = match app.current_card() {
+        Some(card) => card,
+        None => {
+            f.render_widget(block, chunk);
+            return;
+        },
+    };
+    let label = Style::default().fg(Color::Yellow);
+    let now = Utc::now();
+    let today = Local::now().date_naive();
+    let timestamp = |time: Option<DateTime<Utc>>| match time {
+        Some(time) => time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+        None => String::from("-"),
+    };
+
+    let due = match card.due {
+        Some(due) => format!("{} ({})", date::format_date(due), date::describe_due(due, today)),
+        None => String::from("-"),
+    };
+    // Older cards don't know when they moved, so count from when they were made:
+    let in_lane = match card.entered_lane.or(card.created) {
+        Some(since) => date::format_duration(now - since),
+        None => String::from("-"),
+    };
+    let met = Criterion::ALL.iter().filter(|c| card.smart.is_met(**c)).count();
+    let mut smart = vec![Span::styled("SMART: ", label)];
+    smart.extend(smart_badge(card));
+    smart.push(Span::raw(format!(" ({}/{})", met, Criterion::ALL.len())));
+
+    let field = |name: &'static str, value: String| Spans::from(vec![
+        Span::styled(format!("{}: ", name), label),
+        Span::raw(value),
+    ]);
+    let text = vec![
+        field("ID", card.id.clone()),
+        field("Lane", app.lanes[app.current_lane].name.clone()),
+        Spans::from(vec![
+            Span::styled("Priority: ", label),
+            Span::styled(
+                format!("{} {}", card.priority.glyph(), card.priority.name()),
+                priority_style(card.priority),
+            ),
+        ]),
+        field("Due", due),
+        Spans::from(
+            std::iter::once(Span::styled("Tags:", label))
+                .chain(tag_chips(&card.tags))
+                .collect::<Vec<_>>()
+        ),
+        field("Created", timestamp(card.created)),
+        field("Updated", timestamp(card.updated)),
+        field("In lane", in_lane),
+        Spans::from(smart),
+    ];
+    let metadata = Paragraph::new(text)
+        .block(block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(metadata, chunk);
+}
+
+/// Greedy word wrap to `width` columns, at least 1. Words longer than a
+/// row are split, and runs of spaces between words become one.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    for word in line.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let row_len = row.chars().count();
+        if row_len > 0 && row_len + 1 + word.len() > width {
+            rows.push(std::mem::take(&mut row));
+        }
+        while word.len() > width {
+            let rest = word.split_off(width);
+            rows.push(word.into_iter().collect());
+            word = rest;
+        }
+        if !row.is_empty() { row.push(' '); }
+        row.extend(word);
+    }
+    // Blank lines still take up a row:
+    if !row.is_empty() || rows.is_empty() { rows.push(row); }
+    rows
+}
+
+fn draw_progress<B>(f: &mut Frame<B>, chunk: Rect, card: &Card, ratio: f64)
+    where
+        B: Backend,
+{
+    let label = match (&card.progress, card.checklist()) {
+        (Some(progress), _) => progress.to_string(),
+        (None, Some((done, total))) => format!("{}/{} done", done, total),
+        (None, None) => String::new(),
+    };
+    let gauge = Gauge::default()
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title("Progress")
+        )
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+        .ratio(ratio)
+        .label(format!("{} ({:.0}%)", label, ratio * 100.0));
+    f.render_widget(gauge, chunk);
+}
+
+fn draw_editor<B>(f: &mut Frame<B>, chunk: Rect, editor: &CardEditor)
+    where
+        B: Backend,
+{
+    let label = Style::default().fg(Color::Yellow);
+    let mut rows = vec![
+        ListItem::new(Spans::from(vec![
+            Span::styled("Title: ", label),
+            Span::raw(editor.card.title.as_str()),
+        ])),
+        ListItem::new(Spans::from(vec![
+            Span::styled("Priority: ", label),
+            Span::styled(editor.card.priority.name(), priority_style(editor.card.priority)),
+        ])),
+        ListItem::new(Spans::from(vec![
+            Span::styled("Due: ", label),
+            Span::raw(editor.card.due.map(date::format_date).unwrap_or_default()),
+        ])),
+        ListItem::new(Spans::from(vec![
+            Span::styled("Progress: ", label),
+            Span::raw(editor.card.progress.as_ref().map(|p| p.to_string()).unwrap_or_default()),
+        ])),
+        ListItem::new(Spans::from(
+            std::iter::once(Span::styled("Tags:", label))
+                .chain(tag_chips(&editor.card.tags))
+                .collect::<Vec<_>>()
+        )),
+    ];
+    rows.extend(Criterion::ALL.iter().map(|criterion| {
+        ListItem::new(Spans::from(vec![
+            Span::styled(format!("{}: ", criterion.name()), label),
+            Span::raw(editor.card.smart.get(*criterion)),
+        ]))
+    }));
+    rows.extend(editor.card.description
+        .iter()
+        .map(|line| ListItem::new(Spans::from(line.as_str())))
+    );
+
+    let fields = List::new(rows)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .title("Editing")
+        )
+        .highlight_style(Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    let mut state = editor.state.clone();
+    f.render_stateful_widget(fields, chunk, &mut state);
+}
 
-        let description = Paragraph::new(text)
-            .block(Block::default()
-                .borders(Borders::ALL)
-            );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        f.render_widget(description, chunk[0]);
+    #[test]
+    fn lines_wrap_at_word_boundaries() {
+        assert_eq!(wrap("", 10), [""]);
+        assert_eq!(wrap("   ", 10), [""]);
+        assert_eq!(wrap("book the flights", 10), ["book the", "flights"]);
+        assert_eq!(wrap("book   the    flights", 20), ["book the flights"]);
+        // Words longer than a row are split across rows
+        assert_eq!(wrap("see https://example.com", 8), ["see", "https://", "example.", "com"]);
+        assert_eq!(wrap("café", 3), ["caf", "é"]);
+        assert_eq!(wrap("a bc", 1), ["a", "b", "c"]);
+        assert_eq!(wrap("ab", 0), ["a", "b"]);
     }
 }
@@ -0,0 +1,172 @@
+use super::{
+    app::{ self, Card, Criterion, Priority, Progress },
+    date,
+};
+
+use chrono::Local;
+use tui::widgets::ListState;
+
+/// The rows of the editor, in the order they are drawn.
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditorField {
+    Title,
+    Priority,
+    Due,
+    Progress,
+    Tags,
+    Smart(Criterion),
+    // Index into card.description
+    Line(usize),
+}
+
+/// Edits a copy of one card, which is written back into its lane
+/// (at `lane`, `index`) when the editor is closed.
+#[derive(Clone)]
+pub struct CardEditor {
+    pub lane: usize,
+    pub index: usize,
+    pub card: Card,
+    pub state: ListState,
+    // Set while typing into a line that was just inserted,
+    // so backing out of it removes the empty line again:
+    inserted: bool,
+}
+
+impl CardEditor {
+    pub fn new(lane: usize, index: usize, card: Card) -> CardEditor {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        CardEditor {
+            lane,
+            index,
+            card,
+            state,
+            inserted: false,
+        }
+    }
+
+    // Title, Priority, Due, Progress, Tags and the SMART criteria
+    // come before the description
+    const FIRST_LINE: usize = 5 + Criterion::ALL.len();
+
+    fn rows(&self) -> usize {
+        CardEditor::FIRST_LINE + self.card.description.len()
+    }
+
+    pub fn field(&self) -> EditorField {
+        match self.state.selected().unwrap_or(0) {
+            0 => EditorField::Title,
+            1 => EditorField::Priority,
+            2 => EditorField::Due,
+            3 => EditorField::Progress,
+            4 => EditorField::Tags,
+            row if row < CardEditor::FIRST_LINE => EditorField::Smart(Criterion::ALL[row - 5]),
+            row => EditorField::Line(row - CardEditor::FIRST_LINE),
+        }
+    }
+
+    fn select_line(&mut self, line: usize) {
+        self.state.select(Some(line + CardEditor::FIRST_LINE));
+    }
+
+    pub fn next(&mut self) {
+        let row = self.state.selected().unwrap_or(0);
+        if row + 1 < self.rows() { self.state.select(Some(row + 1)); }
+    }
+
+    pub fn previous(&mut self) {
+        let row = self.state.selected().unwrap_or(0);
+        if row > 0 { self.state.select(Some(row - 1)); }
+    }
+
+    /// The current value of the selected field, to pre-fill the input box with.
+    pub fn value(&self) -> String {
+        match self.field() {
+            EditorField::Title => self.card.title.clone(),
+            EditorField::Priority => self.card.priority.name().to_string(),
+            EditorField::Due => self.card.due.map(date::format_date).unwrap_or_default(),
+            EditorField::Progress => self.card.progress
+                .as_ref()
+                .map(Progress::to_string)
+                .unwrap_or_default(),
+            EditorField::Tags => self.card.tags.join(" "),
+            EditorField::Smart(criterion) => self.card.smart.get(criterion).to_string(),
+            EditorField::Line(line) => self.card.description[line].clone(),
+        }
+    }
+
+    /// Store `input` in the selected field. Returns false (and changes
+    /// nothing) if the input isn't valid for that field.
+    pub fn set_value(&mut self, input: &str) -> bool {
+        match self.field() {
+            EditorField::Title => {
+                if input.trim().is_empty() { return false; }
+                self.card.title = String::from(input.trim());
+            },
+            EditorField::Priority => match Priority::parse(input) {
+                Some(priority) => { self.card.priority = priority; },
+                None => { return false; },
+            },
+            // An empty due date clears it
+            EditorField::Due if input.trim().is_empty() => { self.card.due = None; },
+            EditorField::Due => match date::parse_date(input, Local::now().date_naive()) {
+                Some(due) => { self.card.due = Some(due); },
+                None => { return false; },
+            },
+            // An empty progress goes back to following the checklist
+            EditorField::Progress if input.trim().is_empty() => { self.card.progress = None; },
+            EditorField::Progress => match Progress::parse(input) {
+                Some(progress) => { self.card.progress = Some(progress); },
+                None => { return false; },
+            },
+            EditorField::Tags => { self.card.tags = app::parse_tags(input); },
+            EditorField::Smart(criterion) => {
+                *self.card.smart.get_mut(criterion) = String::from(input.trim());
+            },
+            EditorField::Line(line) => {
+                self.card.description[line] = String::from(input);
+            },
+        }
+        self.inserted = false;
+        true
+    }
+
+    /// Stop typing into the selected field without keeping the input.
+    pub fn cancel_value(&mut self) {
+        if self.inserted {
+            self.delete_line();
+        }
+        self.inserted = false;
+    }
+
+    /// Insert an empty description line below the selected one
+    /// (or above it when `above` is set) and select it.
+    pub fn insert_line(&mut self, above: bool) {
+        let line = match self.field() {
+            EditorField::Line(line) if above => line,
+            EditorField::Line(line) => line + 1,
+            // From any of the fields above, new lines go at the top:
+            _ => 0,
+        };
+        self.card.description.insert(line, String::new());
+        self.select_line(line);
+        self.inserted = true;
+    }
+
+    pub fn delete_line(&mut self) {
+        if let EditorField::Line(line) = self.field() {
+            self.card.description.remove(line);
+            if self.state.selected().unwrap_or(0) >= self.rows() {
+                self.previous();
+            }
+        }
+    }
+
+    pub fn raise_priority(&mut self) {
+        self.card.priority = self.card.priority.raise();
+    }
+
+    pub fn lower_priority(&mut self) {
+        self.card.priority = self.card.priority.lower();
+    }
+}
@@ -0,0 +1,192 @@
+use super::{
+    app::{ App, ArchivedCard, Card, SortMode },
+    date,
+    markdown,
+};
+
+use std::{ fs, path::Path };
+
+use serde::Serialize;
+
+/// The formats a board can be exported to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Markdown,
+    // Everything about every card, archived ones too:
+    Json,
+    // One row per card, for spreadsheets:
+    Csv,
+}
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Markdown, Format::Json, Format::Csv];
+    pub const NAMES: &'static str = "markdown, json or csv";
+
+    /// The format called `name`, as given to `export --format`.
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.trim().to_lowercase().as_str() {
+            "markdown" | "md" => Some(Format::Markdown),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    /// The format a file is in, going by its extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?;
+        Format::ALL.iter().copied().find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+/// The open board of `app` in `format`.
+pub fn export(app: &App, format: Format) -> String {
+    match format {
+        Format::Markdown => markdown::export(app.board_name(), &app.lanes),
+        Format::Json => json(app),
+        Format::Csv => csv(app),
+    }
+}
+
+#[derive(Serialize)]
+struct BoardRef<'a> {
+    board: &'a str,
+    lanes: Vec<LaneRef<'a>>,
+    archive: &'a [ArchivedCard],
+}
+#[derive(Serialize)]
+struct LaneRef<'a> {
+    name: &'a str,
+    sort: SortMode,
+    cards: &'a [Card],
+}
+
+// The cards as they are saved, but only the open board's
+fn json(app: &App) -> String {
+    let board = BoardRef {
+        board: app.board_name(),
+        lanes: app.lanes.iter()
+            .map(|lane| LaneRef { name: &lane.name, sort: lane.sort, cards: &lane.cards.items })
+            .collect(),
+        archive: &app.archive,
+    };
+    let mut out = serde_json::to_string_pretty(&board).expect("boards always serialise");
+    out.push('\n');
+    out
+}
+
+const CSV_HEADER: &str = "id,title,lane,priority,tags,due,created,completed";
+
+// A row per card on the lanes. A card is completed when it is in the
+// done lane (see App::done_lane), since it got there.
+fn csv(app: &App) -> String {
+    let done = app.done_lane();
+    let mut out = format!("{}\n", CSV_HEADER);
+    for (index, lane) in app.lanes.iter().enumerate() {
+        for card in &lane.cards.items {
+            let completed = if done == Some(index) { card.entered_lane } else { None };
+            let row = [
+                card.id.clone(),
+                card.title.clone(),
+                lane.name.clone(),
+                card.priority.name().to_lowercase(),
+                card.tags.join(" "),
+                card.due.map(date::format_date).unwrap_or_default(),
+                card.created.map(|created| created.to_rfc3339()).unwrap_or_default(),
+                completed.map(|completed| completed.to_rfc3339()).unwrap_or_default(),
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// Quoted if it has to be, with quotes doubled
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+/// Where the open board is exported to unless another file is given,
+/// e.g. `Main.md`.
+pub fn default_path(app: &App, format: Format) -> String {
+    format!("{}.{}", app.board_name(), format.extension())
+}
+
+/// Write the open board to `path`, in the format its extension asks for.
+/// The result is the message to show either way.
+pub fn export_file(app: &App, path: &str) -> Result<String, String> {
+    let path = Path::new(path.trim());
+    let format = Format::from_path(path).ok_or_else(|| format!(
+        "Can't tell what to export {} as, give it a .md, .json or .csv extension", path.display()
+    ))?;
+    fs::write(path, export(app, format))
+        .map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+    Ok(format!("Exported '{}' to {}", app.board_name(), path.display()))
+}
+
+/// Replace the open board's lanes with the ones in the Markdown file at
+/// `path`, see App::import_lanes.
+pub fn import_file(app: &mut App, path: &str) -> Result<String, String> {
+    if app.readonly {
+        return Err(String::from("The board is open read-only, importing would change it"));
+    }
+    let path = Path::new(path.trim());
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+    let lanes = markdown::import(&text).map_err(|err| format!("{}, {}", path.display(), err))?;
+    app.import_lanes(lanes);
+    Ok(app.message.take().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::app::Priority;
+
+    #[test]
+    fn csv_has_a_row_per_card() {
+        let mut app = App::default();
+        app.add_card("Write the report, \"final\"");
+        app.update_card(|card| {
+            card.priority = Priority::High;
+            card.tags = vec![String::from("work"), String::from("q4")];
+        });
+        app.move_card(2);
+        let csv = export(&app, Format::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines.len(), 2);
+        let card = &app.lanes[2].cards.items[0];
+        assert!(lines[1].starts_with(&format!(
+            "{},\"Write the report, \"\"final\"\"\",Finished,high,work q4,,", card.id
+        )));
+        assert!(lines[1].ends_with(&card.entered_lane.unwrap().to_rfc3339()));
+    }
+
+    #[test]
+    fn json_keeps_everything() {
+        let mut app = App::default();
+        app.add_card("Write the report");
+        app.archive_card();
+        app.add_card("Book flights");
+        let json: serde_json::Value = serde_json::from_str(&export(&app, Format::Json)).unwrap();
+        assert_eq!(json["board"], "Main");
+        assert_eq!(json["lanes"][0]["cards"][0]["title"], "Book flights");
+        assert_eq!(json["archive"][0]["card"]["title"], "Write the report");
+        assert_eq!(Format::from_path(Path::new("report.CSV")), Some(Format::Csv));
+    }
+}
@@ -0,0 +1,265 @@
+use super::app::{ ArchivedCard, Card, Lane, SortMode };
+
+/// How many changes can be undone, unless the config file says otherwise.
+pub const DEFAULT_DEPTH: usize = 100;
+
+/// One change to the lanes. Everything that changes the board goes through
+/// one of these, so it can be undone by applying its `inverse`.
+///
+/// Cards are found by their ID (see App::new_id). The lane and index a card
+/// had when the change was made are only where it is looked for first, and
+/// where it is put back.
+#[derive(Clone)]
+pub enum Change {
+    InsertCard { lane: usize, index: usize, card: Card },
+    RemoveCard { lane: usize, index: usize, card: Card },
+    // Boxed, or every change would be the size of two cards:
+    UpdateCard { lane: usize, index: usize, before: Box<Card>, after: Box<Card> },
+    InsertLane { index: usize, lane: Lane },
+    RemoveLane { index: usize, lane: Lane },
+    RenameLane { index: usize, before: String, after: String },
+    // Swaps two neighbouring lanes, `from` ends up at `to`:
+    MoveLane { from: usize, to: usize },
+    // Moves the card with ID `id` within its lane:
+    ShiftCard { lane: usize, id: String, from: usize, to: usize },
+    // Switches a lane's sort mode and puts its cards in `order`, by ID.
+    // `old_order` is how they were, to undo it:
+    SortLane { lane: usize, before: SortMode, after: SortMode, old_order: Vec<String>, order: Vec<String> },
+    // Takes a card off its lane and into the archive at `archive_index`,
+    // and back again:
+    ArchiveCard { lane: usize, index: usize, archive_index: usize, entry: ArchivedCard },
+    RestoreCard { lane: usize, index: usize, archive_index: usize, entry: ArchivedCard },
+    // Several changes that are undone together, e.g. moving a card
+    // is removing it from one lane and inserting it into another:
+    Group(Vec<Change>),
+}
+
+impl Change {
+    /// Switch lane `index` to `after`, putting its cards in that order.
+    pub fn sort_lane(index: usize, lane: &Lane, after: SortMode) -> Change {
+        let ids = |order: Vec<usize>| -> Vec<String> {
+            order.into_iter().map(|at| lane.cards.items[at].id.clone()).collect()
+        };
+        Change::SortLane {
+            lane: index,
+            before: lane.sort,
+            after,
+            old_order: ids((0..lane.cards.items.len()).collect()),
+            order: ids(lane.sorted_order(after)),
+        }
+    }
+
+    /// Make the change. Returns the lane to focus afterwards and,
+    /// if it matters, which card in it to select.
+    pub fn apply(&self, lanes: &mut Vec<Lane>, archive: &mut Vec<ArchivedCard>) -> (usize, Option<usize>) {
+        match self {
+            Change::InsertCard { lane, index, card } => {
+                let cards = &mut lanes[*lane].cards.items;
+                let index = (*index).min(cards.len());
+                cards.insert(index, card.clone());
+                (*lane, Some(index))
+            },
+            Change::RemoveCard { lane, index, card } => match find(lanes, *lane, *index, &card.id) {
+                Some((lane, index)) => {
+                    lanes[lane].cards.items.remove(index);
+                    (lane, Some(index))
+                },
+                None => (*lane, None),
+            },
+            Change::UpdateCard { lane, index, after, .. } => match find(lanes, *lane, *index, &after.id) {
+                Some((lane, index)) => {
+                    lanes[lane].cards.items[index] = (**after).clone();
+                    (lane, Some(index))
+                },
+                None => (*lane, None),
+            },
+            Change::InsertLane { index, lane } => {
+                lanes.insert(*index, lane.clone());
+                (*index, None)
+            },
+            Change::RemoveLane { index, .. } => {
+                lanes.remove(*index);
+                (*index, None)
+            },
+            Change::RenameLane { index, after, .. } => {
+                lanes[*index].name = after.clone();
+                (*index, None)
+            },
+            Change::MoveLane { from, to } => {
+                lanes.swap(*from, *to);
+                (*to, None)
+            },
+            Change::ShiftCard { lane, id, from, to } => match find(lanes, *lane, *from, id) {
+                Some((lane, from)) => {
+                    let cards = &mut lanes[lane].cards.items;
+                    let card = cards.remove(from);
+                    let to = (*to).min(cards.len());
+                    cards.insert(to, card);
+                    (lane, Some(to))
+                },
+                None => (*lane, None),
+            },
+            Change::SortLane { lane, after, order, .. } => {
+                // The lane's selected card stays selected:
+                let selected = lanes[*lane].cards.state.selected();
+                let focus = self.follow((*lane, selected), lanes);
+                reorder(&mut lanes[*lane].cards.items, order);
+                lanes[*lane].sort = *after;
+                focus
+            },
+            Change::ArchiveCard { lane, index, archive_index, entry } => {
+                let found = find(lanes, *lane, *index, &entry.card.id);
+                if let Some((lane, index)) = found {
+                    lanes[lane].cards.items.remove(index);
+                }
+                archive.insert((*archive_index).min(archive.len()), entry.clone());
+                found.map_or((*lane, None), |(lane, index)| (lane, Some(index)))
+            },
+            Change::RestoreCard { lane, index, archive_index, entry } => {
+                let id = &entry.card.id;
+                let archived = match archive.get(*archive_index) {
+                    Some(archived) if archived.card.id == *id => Some(*archive_index),
+                    _ => archive.iter().position(|archived| archived.card.id == *id),
+                };
+                if let Some(archived) = archived {
+                    archive.remove(archived);
+                }
+                let cards = &mut lanes[*lane].cards.items;
+                let index = (*index).min(cards.len());
+                cards.insert(index, entry.card.clone());
+                (*lane, Some(index))
+            },
+            Change::Group(changes) => {
+                let mut focus = (0, None);
+                for change in changes {
+                    // A sort keeps following the card the group is about
+                    focus = match change {
+                        Change::SortLane { .. } => {
+                            let focus = change.follow(focus, lanes);
+                            change.apply(lanes, archive);
+                            focus
+                        },
+                        _ => change.apply(lanes, archive),
+                    };
+                }
+                focus
+            },
+        }
+    }
+
+    /// Where a selected card ends up after this change, if it is a sort.
+    /// Called with the lanes as they are before the change.
+    pub fn follow(&self, focus: (usize, Option<usize>), lanes: &[Lane]) -> (usize, Option<usize>) {
+        match (self, focus) {
+            (Change::SortLane { lane, order, .. }, (focus_lane, Some(index))) if *lane == focus_lane => {
+                let id = lanes[*lane].cards.items.get(index).map(|card| &card.id);
+                (focus_lane, id.and_then(|id| order.iter().position(|other| other == id)).or(Some(index)))
+            },
+            _ => focus,
+        }
+    }
+
+    /// The change that takes this one back.
+    pub fn inverse(&self) -> Change {
+        match self.clone() {
+            Change::InsertCard { lane, index, card } => Change::RemoveCard { lane, index, card },
+            Change::RemoveCard { lane, index, card } => Change::InsertCard { lane, index, card },
+            Change::UpdateCard { lane, index, before, after } => {
+                Change::UpdateCard { lane, index, before: after, after: before }
+            },
+            Change::InsertLane { index, lane } => Change::RemoveLane { index, lane },
+            Change::RemoveLane { index, lane } => Change::InsertLane { index, lane },
+            Change::RenameLane { index, before, after } => {
+                Change::RenameLane { index, before: after, after: before }
+            },
+            Change::MoveLane { from, to } => Change::MoveLane { from: to, to: from },
+            Change::ShiftCard { lane, id, from, to } => Change::ShiftCard { lane, id, from: to, to: from },
+            Change::SortLane { lane, before, after, old_order, order } => {
+                Change::SortLane { lane, before: after, after: before, old_order: order, order: old_order }
+            },
+            Change::ArchiveCard { lane, index, archive_index, entry } => {
+                Change::RestoreCard { lane, index, archive_index, entry }
+            },
+            Change::RestoreCard { lane, index, archive_index, entry } => {
+                Change::ArchiveCard { lane, index, archive_index, entry }
+            },
+            // Undone back to front:
+            Change::Group(changes) => Change::Group(changes.iter().rev().map(Change::inverse).collect()),
+        }
+    }
+}
+
+// Where the card with ID `id` is: at `index` in `lane` if it is still
+// there, else wherever it has got to since
+fn find(lanes: &[Lane], lane: usize, index: usize, id: &str) -> Option<(usize, usize)> {
+    let at = |lane: usize| lanes[lane].cards.items.iter().position(|card| card.id == id);
+    if lanes.get(lane)?.cards.items.get(index).is_some_and(|card| card.id == id) {
+        return Some((lane, index));
+    }
+    at(lane).map(|index| (lane, index))
+        .or_else(|| (0..lanes.len()).find_map(|other| at(other).map(|index| (other, index))))
+}
+
+// Put `cards` in `order`, by ID. Cards it doesn't name keep their order, after it.
+fn reorder(cards: &mut Vec<Card>, order: &[String]) {
+    let mut rest = std::mem::take(cards);
+    for id in order {
+        if let Some(at) = rest.iter().position(|card| card.id == *id) {
+            cards.push(rest.remove(at));
+        }
+    }
+    cards.append(&mut rest);
+}
+
+/// The undo and redo stacks. Only the last `depth` changes are kept.
+#[derive(Clone)]
+pub struct History {
+    done: Vec<Change>,
+    undone: Vec<Change>,
+    depth: usize,
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::with_depth(DEFAULT_DEPTH)
+    }
+}
+
+impl History {
+    pub fn with_depth(depth: usize) -> History {
+        History {
+            done: Vec::new(),
+            undone: Vec::new(),
+            depth,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Remember a change that was just made. Anything that could
+    /// be redone is forgotten, as it no longer follows on.
+    pub fn record(&mut self, change: Change) {
+        self.undone.clear();
+        self.done.push(change);
+        if self.done.len() > self.depth {
+            self.done.remove(0);
+        }
+    }
+
+    /// The change that undoes the last one, to be applied to the lanes.
+    pub fn undo(&mut self) -> Option<Change> {
+        let change = self.done.pop()?;
+        let inverse = change.inverse();
+        self.undone.push(change);
+        Some(inverse)
+    }
+
+    /// The last undone change, to be applied to the lanes again.
+    pub fn redo(&mut self) -> Option<Change> {
+        let change = self.undone.pop()?;
+        self.done.push(change.clone());
+        Some(change)
+    }
+}
@@ -0,0 +1,562 @@
+use termion::event::Key;
+
+/// The groups of keybindings. Each InputMode reads its keys from one of these;
+/// all of the modes that type into the input box share `Typing`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    Normal,
+    Checklist,
+    Edit,
+    Typing,
+    Help,
+    Confirm,
+    Archive,
+    Filters,
+}
+impl Mode {
+    pub const ALL: [Mode; 8] = [
+        Mode::Normal, Mode::Checklist, Mode::Edit, Mode::Typing, Mode::Help, Mode::Confirm, Mode::Archive,
+        Mode::Filters,
+    ];
+
+    /// The mode's table in the config file, e.g. `[keys.normal]`.
+    pub fn from_name(name: &str) -> Option<Mode> {
+        Mode::ALL.iter().copied().find(|mode| mode.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Normal => "Normal",
+            Mode::Checklist => "Checklist",
+            Mode::Edit => "Edit",
+            Mode::Typing => "Typing",
+            Mode::Help => "Help",
+            Mode::Confirm => "Confirm",
+            Mode::Archive => "Archive",
+            Mode::Filters => "Filters",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    // Normal
+    Quit,
+    Help,
+    NewCard,
+    AddDescription,
+    EditCard,
+    OpenChecklist,
+    PreviousCard,
+    NextCard,
+    FirstCard,
+    LastCard,
+    PreviousLane,
+    NextLane,
+    MoveCardLeft,
+    MoveCardRight,
+    MoveCardUp,
+    MoveCardDown,
+    CycleSort,
+    ToggleIds,
+    RaisePriority,
+    LowerPriority,
+    AddLane,
+    RenameLane,
+    RemoveLane,
+    MoveLaneLeft,
+    MoveLaneRight,
+    NextBoard,
+    PreviousBoard,
+    NewBoard,
+    RenameBoard,
+    DeleteBoard,
+    ExportBoard,
+    ImportBoard,
+    DeleteCard,
+    CopyCard,
+    PasteCard,
+    ArchiveCard,
+    OpenArchive,
+    Search,
+    NextHit,
+    PreviousHit,
+    ClearSearch,
+    SaveFilter,
+    OpenFilters,
+    Undo,
+    Redo,
+    ProgressUp,
+    ProgressDown,
+    ScrollDescriptionUp,
+    ScrollDescriptionDown,
+    // Checklist
+    ToggleItem,
+    ToggleCheckbox,
+    PreviousLine,
+    NextLine,
+    CloseChecklist,
+    // Edit
+    EditField,
+    InsertLineBelow,
+    InsertLineAbove,
+    DeleteLine,
+    PriorityUp,
+    PriorityDown,
+    PreviousField,
+    NextField,
+    CloseEditor,
+    // Typing
+    Submit,
+    Cancel,
+    DeleteChar,
+    CompleteTag,
+    // Help
+    ScrollHelpUp,
+    ScrollHelpDown,
+    PageHelpUp,
+    PageHelpDown,
+    CloseHelp,
+    // Confirm
+    Yes,
+    No,
+    // Archive
+    PreviousArchived,
+    NextArchived,
+    RestoreCard,
+    SearchArchive,
+    CloseArchive,
+    // Filters
+    PreviousFilter,
+    NextFilter,
+    ApplyFilter,
+    DeleteFilter,
+    CloseFilters,
+}
+
+// Every action, the mode it belongs to, its name in the config file and what
+// the help screen says about it. This is also the order the help screen lists them in.
+const ACTIONS: &[(Action, Mode, &str, &str)] = &[
+    (Action::Help, Mode::Normal, "help", "Show this help"),
+    (Action::Quit, Mode::Normal, "quit", "Quit"),
+    (Action::PreviousCard, Mode::Normal, "previous_card", "Select the card above"),
+    (Action::NextCard, Mode::Normal, "next_card", "Select the card below"),
+    (Action::FirstCard, Mode::Normal, "first_card", "Select the first card (or card N with a count)"),
+    (Action::LastCard, Mode::Normal, "last_card", "Select the last card (or card N with a count)"),
+    (Action::PreviousLane, Mode::Normal, "previous_lane", "Go to the lane on the left"),
+    (Action::NextLane, Mode::Normal, "next_lane", "Go to the lane on the right"),
+    (Action::NewCard, Mode::Normal, "new_card", "Create a card in this lane"),
+    (Action::AddDescription, Mode::Normal, "add_description", "Add description lines to the selected card"),
+    (Action::EditCard, Mode::Normal, "edit_card", "Edit the selected card"),
+    (Action::OpenChecklist, Mode::Normal, "open_checklist", "Work through the selected card's checklist"),
+    (Action::MoveCardLeft, Mode::Normal, "move_card_left", "Move the card to the lane on the left"),
+    (Action::MoveCardRight, Mode::Normal, "move_card_right", "Move the card to the lane on the right"),
+    (Action::MoveCardUp, Mode::Normal, "move_card_up", "Move the card up the lane"),
+    (Action::MoveCardDown, Mode::Normal, "move_card_down", "Move the card down the lane"),
+    (Action::CycleSort, Mode::Normal, "cycle_sort", "Sort the lane by hand, priority, due date, created date or title"),
+    (Action::ToggleIds, Mode::Normal, "toggle_ids", "Show or hide the card IDs"),
+    (Action::RaisePriority, Mode::Normal, "raise_priority", "Raise the card's priority"),
+    (Action::LowerPriority, Mode::Normal, "lower_priority", "Lower the card's priority"),
+    (Action::DeleteCard, Mode::Normal, "delete_card", "Delete the card, after asking (it can still be pasted)"),
+    (Action::Search, Mode::Normal, "search", "Search the cards in every lane, showing only the hits"),
+    (Action::NextHit, Mode::Normal, "next_hit", "Go to the next card matching the search"),
+    (Action::PreviousHit, Mode::Normal, "previous_hit", "Go to the previous card matching the search"),
+    (Action::NextBoard, Mode::Normal, "next_board", "Open the board in the next tab (or tab N with a count)"),
+    (Action::PreviousBoard, Mode::Normal, "previous_board", "Open the board in the previous tab"),
+    (Action::NewBoard, Mode::Normal, "new_board", "Create a board in a new tab"),
+    (Action::RenameBoard, Mode::Normal, "rename_board", "Rename the open board"),
+    (Action::DeleteBoard, Mode::Normal, "delete_board", "Delete the open board and everything on it"),
+    (Action::ExportBoard, Mode::Normal, "export_board", "Export the open board to Markdown, JSON or CSV"),
+    (Action::ImportBoard, Mode::Normal, "import_board", "Replace the lanes with those in a Markdown file"),
+    (Action::ClearSearch, Mode::Normal, "clear_search", "Stop searching and show every card"),
+    (Action::SaveFilter, Mode::Normal, "save_filter", "Save the search as a named filter"),
+    (Action::OpenFilters, Mode::Normal, "open_filters", "Pick one of the saved filters"),
+    (Action::ArchiveCard, Mode::Normal, "archive_card", "Move the card into the archive"),
+    (Action::OpenArchive, Mode::Normal, "open_archive", "Browse, search and restore archived cards"),
+    (Action::CopyCard, Mode::Normal, "copy_card", "Copy the card"),
+    (Action::PasteCard, Mode::Normal, "paste_card", "Paste the copied or deleted card below this one"),
+    (Action::Undo, Mode::Normal, "undo", "Undo the last change"),
+    (Action::Redo, Mode::Normal, "redo", "Redo the last undone change"),
+    (Action::ProgressUp, Mode::Normal, "progress_up", "Add one to the card's progress"),
+    (Action::ProgressDown, Mode::Normal, "progress_down", "Take one off the card's progress"),
+    (Action::ScrollDescriptionUp, Mode::Normal, "scroll_description_up", "Scroll the description up"),
+    (Action::ScrollDescriptionDown, Mode::Normal, "scroll_description_down", "Scroll the description down"),
+    (Action::AddLane, Mode::Normal, "add_lane", "Add a lane to the right of this one"),
+    (Action::RenameLane, Mode::Normal, "rename_lane", "Rename this lane"),
+    (Action::RemoveLane, Mode::Normal, "remove_lane", "Remove this lane (it must be empty)"),
+    (Action::MoveLaneLeft, Mode::Normal, "move_lane_left", "Move this lane left"),
+    (Action::MoveLaneRight, Mode::Normal, "move_lane_right", "Move this lane right"),
+
+    (Action::PreviousLine, Mode::Checklist, "previous_line", "Select the line above"),
+    (Action::NextLine, Mode::Checklist, "next_line", "Select the line below"),
+    (Action::ToggleItem, Mode::Checklist, "toggle_item", "Tick or untick the line"),
+    (Action::ToggleCheckbox, Mode::Checklist, "toggle_checkbox", "Add or remove the line's checkbox"),
+    (Action::CloseChecklist, Mode::Checklist, "close_checklist", "Back to the lanes"),
+
+    (Action::PreviousField, Mode::Edit, "previous_field", "Select the field above"),
+    (Action::NextField, Mode::Edit, "next_field", "Select the field below"),
+    (Action::EditField, Mode::Edit, "edit_field", "Change the selected field"),
+    (Action::InsertLineBelow, Mode::Edit, "insert_line_below", "Add a description line below"),
+    (Action::InsertLineAbove, Mode::Edit, "insert_line_above", "Add a description line above"),
+    (Action::DeleteLine, Mode::Edit, "delete_line", "Delete the selected description line"),
+    (Action::PriorityUp, Mode::Edit, "priority_up", "Raise the priority"),
+    (Action::PriorityDown, Mode::Edit, "priority_down", "Lower the priority"),
+    (Action::CloseEditor, Mode::Edit, "close_editor", "Save the card and close the editor"),
+
+    (Action::Submit, Mode::Typing, "submit", "Keep what was typed"),
+    (Action::Cancel, Mode::Typing, "cancel", "Back out without keeping it"),
+    (Action::DeleteChar, Mode::Typing, "delete_char", "Delete the last character"),
+    (Action::CompleteTag, Mode::Typing, "complete_tag", "Complete the tag being typed from the existing ones"),
+
+    (Action::ScrollHelpUp, Mode::Help, "scroll_help_up", "Scroll up"),
+    (Action::ScrollHelpDown, Mode::Help, "scroll_help_down", "Scroll down"),
+    (Action::PageHelpUp, Mode::Help, "page_help_up", "Scroll up a page"),
+    (Action::PageHelpDown, Mode::Help, "page_help_down", "Scroll down a page"),
+    (Action::CloseHelp, Mode::Help, "close_help", "Close the help"),
+
+    (Action::Yes, Mode::Confirm, "yes", "Go ahead"),
+    (Action::No, Mode::Confirm, "no", "Think again"),
+
+    (Action::PreviousArchived, Mode::Archive, "previous_archived", "Select the card above"),
+    (Action::NextArchived, Mode::Archive, "next_archived", "Select the card below"),
+    (Action::RestoreCard, Mode::Archive, "restore_card", "Put the card back on the board"),
+    (Action::SearchArchive, Mode::Archive, "search_archive", "Search the archive"),
+    (Action::CloseArchive, Mode::Archive, "close_archive", "Back to the lanes"),
+
+    (Action::PreviousFilter, Mode::Filters, "previous_filter", "Select the filter above"),
+    (Action::NextFilter, Mode::Filters, "next_filter", "Select the filter below"),
+    (Action::ApplyFilter, Mode::Filters, "apply_filter", "Search with the filter"),
+    (Action::DeleteFilter, Mode::Filters, "delete_filter", "Forget the filter"),
+    (Action::CloseFilters, Mode::Filters, "close_filters", "Back to the lanes"),
+];
+
+impl Action {
+    fn entry(self) -> &'static (Action, Mode, &'static str, &'static str) {
+        ACTIONS.iter()
+            .find(|(action, _, _, _)| *action == self)
+            .expect("every action is listed in ACTIONS")
+    }
+
+    /// The action called `name` in `mode`, as written in the config file.
+    pub fn from_name(mode: Mode, name: &str) -> Option<Action> {
+        ACTIONS.iter()
+            .find(|(_, action_mode, action_name, _)| *action_mode == mode && *action_name == name)
+            .map(|(action, _, _, _)| *action)
+    }
+
+    pub fn mode(self) -> Mode {
+        self.entry().1
+    }
+
+    pub fn name(self) -> &'static str {
+        self.entry().2
+    }
+
+    pub fn description(self) -> &'static str {
+        self.entry().3
+    }
+}
+
+/// Which keys do what, in which mode. A binding is a sequence of keys
+/// pressed one after the other, like `g g`; most are a single key.
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: Vec<(Vec<Key>, Action)>,
+}
+
+/// What the keys pressed so far mean.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Lookup {
+    Action(Action),
+    // The start of a longer sequence, wait for the next key:
+    Prefix,
+    None,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        // Written the same way as in the config file:
+        let bindings = [
+            ("?", Action::Help),
+            // 'h' opened the help until it became the vim key for the lane on the left:
+            ("F1", Action::Help),
+            ("q", Action::Quit),
+            ("Up", Action::PreviousCard),
+            ("k", Action::PreviousCard),
+            ("Down", Action::NextCard),
+            ("j", Action::NextCard),
+            ("gg", Action::FirstCard),
+            ("Home", Action::FirstCard),
+            ("G", Action::LastCard),
+            ("End", Action::LastCard),
+            ("Left", Action::PreviousLane),
+            ("h", Action::PreviousLane),
+            ("Right", Action::NextLane),
+            ("l", Action::NextLane),
+            ("t", Action::NewCard),
+            ("a", Action::AddDescription),
+            ("Enter", Action::EditCard),
+            ("c", Action::OpenChecklist),
+            // Plenty of terminals can't send Ctrl-, or Ctrl-. at all:
+            ("H", Action::MoveCardLeft),
+            ("<", Action::MoveCardLeft),
+            ("Ctrl-,", Action::MoveCardLeft),
+            ("L", Action::MoveCardRight),
+            (">", Action::MoveCardRight),
+            ("Ctrl-.", Action::MoveCardRight),
+            ("K", Action::MoveCardUp),
+            ("J", Action::MoveCardDown),
+            ("s", Action::CycleSort),
+            ("i", Action::ToggleIds),
+            ("+", Action::RaisePriority),
+            ("=", Action::RaisePriority),
+            ("-", Action::LowerPriority),
+            ("dd", Action::DeleteCard),
+            ("yy", Action::CopyCard),
+            ("p", Action::PasteCard),
+            ("/", Action::Search),
+            ("n", Action::NextHit),
+            ("N", Action::PreviousHit),
+            ("Esc", Action::ClearSearch),
+            ("S", Action::SaveFilter),
+            ("F", Action::OpenFilters),
+            ("z", Action::ArchiveCard),
+            ("Z", Action::OpenArchive),
+            ("u", Action::Undo),
+            ("Ctrl-r", Action::Redo),
+            ("]", Action::ProgressUp),
+            ("[", Action::ProgressDown),
+            ("PageUp", Action::ScrollDescriptionUp),
+            ("PageDown", Action::ScrollDescriptionDown),
+            ("A", Action::AddLane),
+            ("R", Action::RenameLane),
+            ("X", Action::RemoveLane),
+            ("{", Action::MoveLaneLeft),
+            ("}", Action::MoveLaneRight),
+            ("Tab", Action::NextBoard),
+            ("gt", Action::NextBoard),
+            ("Shift-Tab", Action::PreviousBoard),
+            ("g T", Action::PreviousBoard),
+            ("bn", Action::NewBoard),
+            ("br", Action::RenameBoard),
+            ("bd", Action::DeleteBoard),
+            ("E", Action::ExportBoard),
+            ("I", Action::ImportBoard),
+
+            ("Up", Action::PreviousLine),
+            ("k", Action::PreviousLine),
+            ("Down", Action::NextLine),
+            ("j", Action::NextLine),
+            ("Space", Action::ToggleItem),
+            ("Enter", Action::ToggleItem),
+            ("b", Action::ToggleCheckbox),
+            ("Esc", Action::CloseChecklist),
+
+            ("Up", Action::PreviousField),
+            ("k", Action::PreviousField),
+            ("Down", Action::NextField),
+            ("j", Action::NextField),
+            ("Enter", Action::EditField),
+            ("o", Action::InsertLineBelow),
+            ("O", Action::InsertLineAbove),
+            ("x", Action::DeleteLine),
+            ("dd", Action::DeleteLine),
+            ("Delete", Action::DeleteLine),
+            ("+", Action::PriorityUp),
+            ("-", Action::PriorityDown),
+            ("Esc", Action::CloseEditor),
+
+            ("Enter", Action::Submit),
+            ("Esc", Action::Cancel),
+            ("Backspace", Action::DeleteChar),
+            ("Tab", Action::CompleteTag),
+
+            ("Up", Action::ScrollHelpUp),
+            ("k", Action::ScrollHelpUp),
+            ("Down", Action::ScrollHelpDown),
+            ("j", Action::ScrollHelpDown),
+            ("PageUp", Action::PageHelpUp),
+            ("PageDown", Action::PageHelpDown),
+            ("Esc", Action::CloseHelp),
+            ("q", Action::CloseHelp),
+            ("?", Action::CloseHelp),
+            ("F1", Action::CloseHelp),
+
+            ("y", Action::Yes),
+            ("Enter", Action::Yes),
+            ("n", Action::No),
+            ("Esc", Action::No),
+
+            ("Up", Action::PreviousArchived),
+            ("k", Action::PreviousArchived),
+            ("Down", Action::NextArchived),
+            ("j", Action::NextArchived),
+            ("r", Action::RestoreCard),
+            ("Enter", Action::RestoreCard),
+            ("/", Action::SearchArchive),
+            ("Esc", Action::CloseArchive),
+            ("q", Action::CloseArchive),
+
+            ("Up", Action::PreviousFilter),
+            ("k", Action::PreviousFilter),
+            ("Down", Action::NextFilter),
+            ("j", Action::NextFilter),
+            ("Enter", Action::ApplyFilter),
+            ("dd", Action::DeleteFilter),
+            ("x", Action::DeleteFilter),
+            ("Esc", Action::CloseFilters),
+            ("q", Action::CloseFilters),
+        ];
+        let bindings = bindings.iter()
+            .map(|(keys, action)| (parse_sequence(keys).expect("default keys are valid"), *action))
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// What the `keys` pressed so far do in `mode`.
+    pub fn lookup(&self, mode: Mode, keys: &[Key]) -> Lookup {
+        let mut bindings = self.bindings.iter().filter(|(_, action)| action.mode() == mode);
+        if let Some((_, action)) = bindings.clone().find(|(bound, _)| bound.as_slice() == keys) {
+            return Lookup::Action(*action);
+        }
+        if bindings.any(|(bound, _)| bound.starts_with(keys)) {
+            Lookup::Prefix
+        } else {
+            Lookup::None
+        }
+    }
+
+    /// Every key sequence bound to `action`, in the order they were bound.
+    pub fn keys(&self, action: Action) -> Vec<&[Key]> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| keys.as_slice())
+            .collect()
+    }
+
+    /// The actions of `mode` in help screen order, with the keys bound to them.
+    pub fn help(&self, mode: Mode) -> Vec<(Action, Vec<&[Key]>)> {
+        ACTIONS.iter()
+            .filter(|(_, action_mode, _, _)| *action_mode == mode)
+            .map(|(action, _, _, _)| (*action, self.keys(*action)))
+            .collect()
+    }
+
+    /// Replace the keys bound to `action`. An empty list unbinds it.
+    pub fn bind(&mut self, action: Action, sequences: &[Vec<Key>]) {
+        self.bindings.retain(|(_, bound)| *bound != action);
+        self.bindings.extend(sequences.iter().map(|keys| (keys.clone(), action)));
+    }
+
+    /// Pairs of bindings in the same mode where the first sequence is the same
+    /// as the second or the start of it, so one of them can never be used.
+    pub fn conflicts(&self) -> Vec<(&[Key], Action, &[Key], Action)> {
+        let mut conflicts = Vec::new();
+        for (index, (keys, action)) in self.bindings.iter().enumerate() {
+            for (other_keys, other) in &self.bindings[index + 1..] {
+                if other == action || other.mode() != action.mode() { continue; }
+                if other_keys.starts_with(keys) {
+                    conflicts.push((keys.as_slice(), *action, other_keys.as_slice(), *other));
+                } else if keys.starts_with(other_keys) {
+                    conflicts.push((other_keys.as_slice(), *other, keys.as_slice(), *action));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Reads a key sequence: key names separated by spaces (`g g`, `Ctrl-x Ctrl-s`).
+/// Runs of plain lowercase keys can also be written together, like `gg` or `dd`.
+pub fn parse_sequence(name: &str) -> Option<Vec<Key>> {
+    let mut keys = Vec::new();
+    for part in name.split_whitespace() {
+        match parse_key(part) {
+            Some(key) => keys.push(key),
+            // Anything with capitals or dashes is meant as a key name,
+            // so a typo like "Entr" is an error rather than five keys:
+            None if part.chars().all(is_plain) => keys.extend(part.chars().map(Key::Char)),
+            None => return None,
+        }
+    }
+    if keys.is_empty() { None } else { Some(keys) }
+}
+
+/// How a key sequence is written on the help screen, the inverse of `parse_sequence`.
+pub fn sequence_name(keys: &[Key]) -> String {
+    let plain = |key: &Key| matches!(key, Key::Char(c) if is_plain(*c));
+    if keys.len() > 1 && keys.iter().all(plain) {
+        keys.iter().map(|key| key_name(*key)).collect()
+    } else {
+        keys.iter().map(|key| key_name(*key)).collect::<Vec<_>>().join(" ")
+    }
+}
+
+fn is_plain(c: char) -> bool {
+    c.is_ascii_graphic() && !c.is_ascii_uppercase() && c != '-'
+}
+
+/// Reads a key written the way `key_name` writes it:
+/// `q`, `Enter`, `Space`, `Ctrl-r`, `Alt-x`, `F1`, `PageDown`...
+pub fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(rest) = name.strip_prefix("Ctrl-") {
+        return single(rest).map(Key::Ctrl);
+    }
+    if let Some(rest) = name.strip_prefix("Alt-") {
+        return single(rest).map(Key::Alt);
+    }
+    let key = match name {
+        "Enter" => Key::Char('\n'),
+        "Tab" => Key::Char('\t'),
+        "Space" => Key::Char(' '),
+        "Shift-Tab" => Key::BackTab,
+        "Esc" => Key::Esc,
+        "Backspace" => Key::Backspace,
+        "Delete" => Key::Delete,
+        "Insert" => Key::Insert,
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        _ => match name.strip_prefix('F').map(str::parse) {
+            Some(Ok(n)) => Key::F(n),
+            _ => return None,
+        },
+    };
+    Some(key)
+}
+
+/// How a key is written on the help screen.
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\n') => String::from("Enter"),
+        Key::Char('\t') => String::from("Tab"),
+        Key::Char(' ') => String::from("Space"),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(n) => format!("F{}", n),
+        Key::BackTab => String::from("Shift-Tab"),
+        Key::PageUp => String::from("PageUp"),
+        Key::PageDown => String::from("PageDown"),
+        other => format!("{:?}", other),
+    }
+}
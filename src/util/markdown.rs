@@ -0,0 +1,203 @@
+use super::{
+    app::{ self, Card, Lane, Priority },
+    date,
+};
+
+use std::{ error::Error, fmt };
+
+use chrono::Local;
+
+/// A board as Markdown, to review in a PR or edit by hand:
+///
+/// ```markdown
+/// # Main
+///
+/// ## Todo
+///
+/// - Write the report `id:k83t priority:high due:2026-11-01 tag:work`
+///   - [x] Outline
+///   - [ ] First draft
+///   - Ask Sam about the numbers
+/// ```
+///
+/// Each lane is a `##` heading and each card a bullet under it, with its
+/// description lines nested below. What else there is to know about a card
+/// goes at the end of its bullet, written the way it is searched for.
+/// Anything else about a card (SMART criteria, progress, timestamps)
+/// is only kept in the board file.
+pub fn export(board: &str, lanes: &[Lane]) -> String {
+    let mut out = format!("# {}\n", board);
+    for lane in lanes {
+        out.push_str(&format!("\n## {}\n", lane.name));
+        if !lane.cards.items.is_empty() { out.push('\n'); }
+        for card in &lane.cards.items {
+            out.push_str(&format!("- {}", card.title));
+            let fields = fields(card);
+            if !fields.is_empty() {
+                out.push_str(&format!(" `{}`", fields.join(" ")));
+            }
+            out.push('\n');
+            for line in &card.description {
+                out.push_str(&format!("  - {}\n", line).replace("  - \n", "  -\n"));
+            }
+        }
+    }
+    out
+}
+
+// `field:value` for everything that isn't the default
+fn fields(card: &Card) -> Vec<String> {
+    let mut fields = Vec::new();
+    if !card.id.is_empty() {
+        fields.push(format!("id:{}", card.id));
+    }
+    if card.priority != Priority::default() {
+        fields.push(format!("priority:{}", card.priority.name().to_lowercase()));
+    }
+    if let Some(due) = card.due {
+        fields.push(format!("due:{}", date::format_date(due)));
+    }
+    fields.extend(card.tags.iter().map(|tag| format!("tag:{}", tag)));
+    fields
+}
+
+/// What is wrong with a Markdown board, and on which line (counting from 1).
+#[derive(Debug)]
+pub struct MarkdownError {
+    pub line: usize,
+    pub problem: String,
+}
+impl fmt::Display for MarkdownError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.problem)
+    }
+}
+impl Error for MarkdownError {}
+
+/// Read back what `export` writes. The `#` title is skipped, the lanes
+/// and cards are new: it is up to the caller to match them to what is
+/// already on the board.
+pub fn import(text: &str) -> Result<Vec<Lane>, MarkdownError> {
+    let mut lanes: Vec<Lane> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let error = |problem: &str| MarkdownError { line: number + 1, problem: String::from(problem) };
+        if line.trim().is_empty() || line.starts_with("# ") {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("## ") {
+            lanes.push(Lane::new(name.trim()));
+            continue;
+        }
+        // Description lines are indented under their card:
+        let indented = line.strip_prefix("  ").or_else(|| line.strip_prefix('\t'));
+        if let Some(nested) = indented {
+            let text = bullet(nested).ok_or_else(|| error("description lines have to be bullets, '  - ...'"))?;
+            let card = lanes.last_mut()
+                .and_then(|lane| lane.cards.items.last_mut())
+                .ok_or_else(|| error("a description line has to come after its card"))?;
+            card.description.push(String::from(text));
+            continue;
+        }
+        let text = bullet(line).ok_or_else(|| {
+            error("expected a '## lane' heading, a '- card' bullet or an indented description line")
+        })?;
+        let lane = lanes.last_mut().ok_or_else(|| error("a card has to come after its lane's '## heading'"))?;
+        let card = parse_card(text).map_err(|problem| error(&problem))?;
+        lane.cards.items.push(card);
+    }
+    if lanes.is_empty() {
+        let line = text.lines().count().max(1);
+        return Err(MarkdownError { line, problem: String::from("there are no lanes, each starts with a '## heading'") });
+    }
+    Ok(lanes)
+}
+
+// The text of a `- ` or `* ` bullet. An empty bullet is an empty line.
+fn bullet(line: &str) -> Option<&str> {
+    if line == "-" || line == "*" { return Some(""); }
+    line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))
+}
+
+// `Title `field:value ...``
+fn parse_card(text: &str) -> Result<Card, String> {
+    let (title, fields) = match text.trim_end().strip_suffix('`').and_then(|rest| rest.rsplit_once(" `")) {
+        Some((title, fields)) => (title.trim(), fields),
+        None => (text.trim(), ""),
+    };
+    if title.is_empty() {
+        return Err(String::from("cards need a title"));
+    }
+    let mut card = Card::new(title);
+    for field in fields.split_whitespace() {
+        let (name, value) = field.split_once(':').unwrap_or((field, ""));
+        match name {
+            "id" => { card.id = value.to_lowercase(); },
+            "priority" => {
+                card.priority = Priority::parse(value)
+                    .ok_or_else(|| format!("'{}' isn't a priority", value))?;
+            },
+            "due" => {
+                card.due = Some(date::parse_date(value, Local::now().date_naive())
+                    .ok_or_else(|| format!("'{}' isn't a date", value))?);
+            },
+            "tag" => { card.tags.extend(app::parse_tags(value)); },
+            _ => return Err(format!("'{}' isn't something a card has, try id, priority, due or tag", field)),
+        }
+    }
+    Ok(card)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    #[test]
+    fn boards_survive_the_round_trip() {
+        let card = Card {
+            id: String::from("k83t"),
+            priority: Priority::High,
+            due: NaiveDate::from_ymd_opt(2026, 11, 1),
+            tags: vec![String::from("work")],
+            description: vec![
+                String::from("[x] Outline"),
+                String::new(),
+                String::from("Ask Sam about the numbers"),
+            ],
+            ..Card::new("Write the report")
+        };
+        let lanes = vec![Lane::with_cards("Todo", vec![card]), Lane::new("Done")];
+        let text = export("Main", &lanes);
+        assert_eq!(text, "# Main\n\n## Todo\n\n\
+            - Write the report `id:k83t priority:high due:2026-11-01 tag:work`\n\
+            \x20 - [x] Outline\n\
+            \x20 -\n\
+            \x20 - Ask Sam about the numbers\n\
+            \n## Done\n");
+
+        let imported = import(&text).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[1].name, "Done");
+        let card = &imported[0].cards.items[0];
+        assert_eq!(card.title, "Write the report");
+        assert_eq!(card.id, "k83t");
+        assert_eq!(card.priority, Priority::High);
+        assert_eq!(card.tags, vec!["work"]);
+        assert_eq!(card.description, lanes[0].cards.items[0].description);
+        assert_eq!(card.checklist(), Some((1, 1)));
+    }
+
+    #[test]
+    fn hand_edits_are_checked() {
+        let lanes = import("## Todo\n* Plain card\n\t- [ ] with a tab\n").unwrap();
+        assert_eq!(lanes[0].cards.items[0].description, vec!["[ ] with a tab"]);
+
+        let error = |text: &str| import(text).err().expect("the import should fail");
+        assert_eq!(error("- Card before any lane\n").line, 1);
+        assert_eq!(error("## Todo\n  - Line before any card\n").line, 2);
+        assert!(error("## Todo\n- Card `priority:soon`\n").problem.contains("soon"));
+        assert_eq!(error("## Todo\nJust some text\n").line, 2);
+        assert_eq!(error("# Main\n\n").line, 2);
+    }
+}
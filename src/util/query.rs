@@ -0,0 +1,256 @@
+use super::{
+    app::{ Card, Priority },
+    date,
+};
+
+use std::cmp::Ordering;
+
+use chrono::NaiveDate;
+use serde::{ Deserialize, Serialize };
+
+/// A parsed search, e.g. `report priority:>=2 tag:work due:<7d lane:"In Progress" -tag:blocked`.
+/// Terms are separated by spaces and all of them have to match.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Filter {
+    All(Vec<Filter>),
+    Not(Box<Filter>),
+    // Words without a field search the title and description:
+    Text(String),
+    Title(String),
+    Lane(String),
+    Tag(String),
+    Id(String),
+    Priority(Ordering, bool, Priority),
+    Due(DueFilter),
+}
+
+/// What `due:` can ask for.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DueFilter {
+    // Compared with `ordering`, or equal to it as well when the bool is set:
+    Date(Ordering, bool, NaiveDate),
+    Overdue,
+    // Any due date at all, or none:
+    Set(bool),
+}
+
+/// A query kept under a name, to be picked again later.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedFilter {
+    pub name: String,
+    pub query: String,
+}
+
+// What can come before a colon; anything else is just text, like 10:30
+const FIELDS: [&str; 7] = ["title", "lane", "tag", "id", "priority", "p", "due"];
+
+impl Filter {
+    /// Whether `card`, in the lane called `lane`, matches.
+    pub fn matches(&self, card: &Card, lane: &str, today: NaiveDate) -> bool {
+        match self {
+            Filter::All(filters) => filters.iter().all(|filter| filter.matches(card, lane, today)),
+            Filter::Not(filter) => !filter.matches(card, lane, today),
+            Filter::Text(text) => card.matches(text),
+            Filter::Title(text) => card.title.to_lowercase().contains(text),
+            Filter::Lane(name) => lane.to_lowercase().contains(name),
+            Filter::Tag(tag) => card.has_tag(tag),
+            Filter::Id(id) => card.id == *id,
+            Filter::Priority(ordering, or_equal, priority) => {
+                compare(&card.priority, *ordering, *or_equal, priority)
+            },
+            Filter::Due(DueFilter::Date(ordering, or_equal, date)) => {
+                card.due.is_some_and(|due| compare(&due, *ordering, *or_equal, date))
+            },
+            Filter::Due(DueFilter::Overdue) => card.due.is_some_and(|due| due < today),
+            Filter::Due(DueFilter::Set(set)) => card.due.is_some() == *set,
+        }
+    }
+
+    /// The plain words being searched for, to pick out where they match.
+    pub fn words(&self) -> Vec<&str> {
+        match self {
+            Filter::All(filters) => filters.iter().flat_map(Filter::words).collect(),
+            Filter::Text(text) | Filter::Title(text) => vec![text.as_str()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn compare<T: Ord>(value: &T, ordering: Ordering, or_equal: bool, with: &T) -> bool {
+    let found = value.cmp(with);
+    found == ordering || (or_equal && found == Ordering::Equal)
+}
+
+/// Parse a query. An empty one matches every card. The error says
+/// what is wrong, to be shown to whoever typed it.
+pub fn parse(query: &str, today: NaiveDate) -> Result<Filter, String> {
+    let terms = split_terms(query)?;
+    let mut filters = Vec::new();
+    for term in terms {
+        let (negated, term) = match term.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+            _ => (false, term),
+        };
+        let filter = parse_term(&term, today)?;
+        filters.push(if negated { Filter::Not(Box::new(filter)) } else { filter });
+    }
+    Ok(Filter::All(filters))
+}
+
+// Split on spaces, except inside double quotes, which are dropped:
+// `lane:"In Progress" x` is `lane:In Progress` and `x`
+fn split_terms(query: &str) -> Result<Vec<String>, String> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => { quoted = !quoted; },
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() { terms.push(std::mem::take(&mut term)); }
+            },
+            c => { term.push(c); },
+        }
+    }
+    if quoted {
+        return Err(String::from("A quote is missing its closing \""));
+    }
+    if !term.is_empty() { terms.push(term); }
+    Ok(terms)
+}
+
+fn parse_term(term: &str, today: NaiveDate) -> Result<Filter, String> {
+    let (field, value) = match term.split_once(':') {
+        Some((field, value)) if FIELDS.contains(&field.to_lowercase().as_str()) => {
+            (field.to_lowercase(), value.trim())
+        },
+        _ => return Ok(Filter::Text(term.to_lowercase())),
+    };
+    if value.is_empty() {
+        return Err(format!("'{}:' needs something after the colon", field));
+    }
+    match field.as_str() {
+        "title" => Ok(Filter::Title(value.to_lowercase())),
+        "lane" => Ok(Filter::Lane(value.to_lowercase())),
+        "tag" => Ok(Filter::Tag(value.trim_start_matches('#').to_lowercase())),
+        "id" => Ok(Filter::Id(value.trim_start_matches('#').to_lowercase())),
+        "priority" | "p" => {
+            let (ordering, or_equal, value) = split_comparison(value);
+            match Priority::parse(value) {
+                Some(priority) => Ok(Filter::Priority(ordering, or_equal, priority)),
+                None => Err(format!(
+                    "'{}' isn't a priority, use low, medium, high, urgent or 0-3", value
+                )),
+            }
+        },
+        // The last of FIELDS:
+        _ => parse_due(value, today).map(Filter::Due),
+    }
+}
+
+fn parse_due(value: &str, today: NaiveDate) -> Result<DueFilter, String> {
+    match value.to_lowercase().as_str() {
+        "overdue" => return Ok(DueFilter::Overdue),
+        "none" => return Ok(DueFilter::Set(false)),
+        "any" => return Ok(DueFilter::Set(true)),
+        _ => {},
+    }
+    let (ordering, or_equal, value) = split_comparison(value);
+    // `7d` is short for `+7d`, a week from today:
+    let date = date::parse_date(value, today)
+        .or_else(|| date::parse_date(&format!("+{}", value), today));
+    match date {
+        Some(date) => Ok(DueFilter::Date(ordering, or_equal, date)),
+        None => Err(format!(
+            "'{}' isn't a date, try 2026-11-01, today, fri, 7d, overdue or none", value
+        )),
+    }
+}
+
+// `>=2` is (Greater, or equal, "2"); no operator means equal.
+fn split_comparison(value: &str) -> (Ordering, bool, &str) {
+    let operators = [
+        (">=", Ordering::Greater, true),
+        ("<=", Ordering::Less, true),
+        (">", Ordering::Greater, false),
+        ("<", Ordering::Less, false),
+        ("=", Ordering::Equal, true),
+    ];
+    for (operator, ordering, or_equal) in operators.iter() {
+        if let Some(rest) = value.strip_prefix(operator) {
+            return (*ordering, *or_equal, rest.trim());
+        }
+    }
+    (Ordering::Equal, true, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Duration;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()
+    }
+
+    fn card(title: &str, priority: Priority, due: Option<NaiveDate>) -> Card {
+        Card { priority, due, ..Card::new(title) }
+    }
+
+    #[test]
+    fn queries_parse_into_filters() {
+        let filter = parse("report priority:>=2 lane:\"In Progress\" -due:none -tag:Blocked", today()).unwrap();
+        assert_eq!(filter, Filter::All(vec![
+            Filter::Text(String::from("report")),
+            Filter::Priority(Ordering::Greater, true, Priority::High),
+            Filter::Lane(String::from("in progress")),
+            Filter::Not(Box::new(Filter::Due(DueFilter::Set(false)))),
+            Filter::Not(Box::new(Filter::Tag(String::from("blocked")))),
+        ]));
+    }
+
+    #[test]
+    fn filters_match_cards() {
+        let soon = Some(today() + Duration::days(3));
+        let filter = parse("priority:>=high due:<7d lane:progress", today()).unwrap();
+        assert!(filter.matches(&card("a", Priority::Urgent, soon), "In Progress", today()));
+        assert!(!filter.matches(&card("b", Priority::Medium, soon), "In Progress", today()));
+        assert!(!filter.matches(&card("c", Priority::High, None), "In Progress", today()));
+        assert!(!filter.matches(&card("d", Priority::High, soon), "Todo", today()));
+
+        let overdue = parse("due:overdue -title:draft", today()).unwrap();
+        let yesterday = Some(today() - Duration::days(1));
+        assert!(overdue.matches(&card("Final report", Priority::Low, yesterday), "Todo", today()));
+        assert!(!overdue.matches(&card("Draft report", Priority::Low, yesterday), "Todo", today()));
+
+        let tagged = parse("tag:work -tag:blocked", today()).unwrap();
+        let work = Card { tags: vec![String::from("work")], ..Card::new("a") };
+        let blocked = Card { tags: vec![String::from("work"), String::from("blocked")], ..Card::new("b") };
+        assert!(tagged.matches(&work, "Todo", today()));
+        assert!(!tagged.matches(&blocked, "Todo", today()));
+    }
+
+    #[test]
+    fn colons_in_plain_text_are_searched_for() {
+        let time = parse("10:30", today()).unwrap();
+        assert_eq!(time, Filter::All(vec![Filter::Text(String::from("10:30"))]));
+        let meeting = Card {
+            description: vec![String::from("notes at https://example.com/notes")],
+            ..Card::new("Meeting at 10:30")
+        };
+        for search in ["10:30", "https://example.com", "-colour:red", "Title:meeting"] {
+            assert!(parse(search, today()).unwrap().matches(&meeting, "Todo", today()), "{}", search);
+        }
+    }
+
+    #[test]
+    fn mistakes_are_explained() {
+        assert!(parse("priority:>=soon", today()).is_err());
+        assert!(parse("due:", today()).is_err());
+        assert!(parse("lane:\"In Progress", today()).is_err());
+        // Typed one key at a time in the live search, so it must not panic
+        assert!(parse("due:<99999999", today()).unwrap_err().contains("99999999"));
+        assert!(parse("due:+99999999w", today()).is_err());
+    }
+}
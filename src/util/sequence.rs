@@ -0,0 +1,118 @@
+use super::keymap::{ self, Action, Keymap, Lookup, Mode };
+
+use termion::event::Key;
+
+// Counts above this are almost certainly a slip of the finger
+const MAX_COUNT: usize = 999;
+
+/// Turns the single keys coming from `Events` into actions, vim style:
+/// a count like `3` followed by a key sequence like `j` or `d d`.
+#[derive(Clone, Default)]
+pub struct KeySequence {
+    count: Option<usize>,
+    keys: Vec<Key>,
+}
+
+/// What a key press amounted to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Parsed {
+    // Part of a count or a longer sequence, nothing to do yet:
+    Pending,
+    // The count, if one was typed before the keys:
+    Action(Action, Option<usize>),
+    // Not bound to anything; typing modes type it.
+    Unbound(Key),
+}
+
+impl KeySequence {
+    /// Add the next key pressed in `mode`.
+    pub fn push(&mut self, keymap: &Keymap, mode: Mode, key: Key) -> Parsed {
+        // Digits are typed as they are in the input box, and a digit that is
+        // bound to something itself is never the start of a count:
+        if mode != Mode::Typing && self.keys.is_empty() {
+            if let Key::Char(digit @ '0'..='9') = key {
+                // ...and a leading 0 isn't a count either, as in vim.
+                let counting = digit != '0' || self.count.is_some();
+                if counting && keymap.lookup(mode, &[key]) == Lookup::None {
+                    let digit = digit.to_digit(10).unwrap_or(0) as usize;
+                    self.count = Some((self.count.unwrap_or(0) * 10 + digit).min(MAX_COUNT));
+                    return Parsed::Pending;
+                }
+            }
+        }
+
+        self.keys.push(key);
+        match keymap.lookup(mode, &self.keys) {
+            Lookup::Action(action) => {
+                self.keys.clear();
+                Parsed::Action(action, self.count.take())
+            },
+            Lookup::Prefix => Parsed::Pending,
+            Lookup::None => {
+                // A sequence that went nowhere (`d` then `j`) is dropped,
+                // but the last key gets a chance on its own:
+                let started = self.keys.len() > 1;
+                self.clear();
+                if started {
+                    self.push(keymap, mode, key)
+                } else {
+                    Parsed::Unbound(key)
+                }
+            },
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.count = None;
+        self.keys.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count.is_none() && self.keys.is_empty()
+    }
+
+    /// What has been typed so far, e.g. `3d`.
+    pub fn pending(&self) -> String {
+        let count = self.count.map(|count| count.to_string()).unwrap_or_default();
+        format!("{}{}", count, keymap::sequence_name(&self.keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(keys: &str) -> Vec<Parsed> {
+        let keymap = Keymap::default();
+        let mut sequence = KeySequence::default();
+        keys.chars()
+            .map(|c| sequence.push(&keymap, Mode::Normal, Key::Char(c)))
+            .collect()
+    }
+
+    #[test]
+    fn counts_come_before_the_keys() {
+        assert_eq!(feed("12j"), vec![
+            Parsed::Pending,
+            Parsed::Pending,
+            Parsed::Action(Action::NextCard, Some(12)),
+        ]);
+    }
+
+    #[test]
+    fn sequences_wait_for_their_last_key() {
+        assert_eq!(feed("gg"), vec![Parsed::Pending, Parsed::Action(Action::FirstCard, None)]);
+        assert_eq!(feed("2dd"), vec![
+            Parsed::Pending,
+            Parsed::Pending,
+            Parsed::Action(Action::DeleteCard, Some(2)),
+        ]);
+    }
+
+    #[test]
+    fn broken_sequences_start_over() {
+        // `d` `j` isn't anything, but the `j` still moves down
+        assert_eq!(feed("dj"), vec![Parsed::Pending, Parsed::Action(Action::NextCard, None)]);
+        assert_eq!(feed("3d%"), vec![Parsed::Pending, Parsed::Pending, Parsed::Unbound(Key::Char('%'))]);
+    }
+}
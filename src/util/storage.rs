@@ -0,0 +1,332 @@
+use super::{
+    app::{ App, ArchivedCard, Board, Card, Lane, SortMode, DEFAULT_BOARD, DEFAULT_LANES },
+    query::SavedFilter,
+};
+
+use std::{
+    env,
+    error::Error,
+    fmt,
+    fs,
+    io,
+    path::{ Path, PathBuf },
+    time::SystemTime,
+};
+
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+
+/// Bump this whenever the on-disk shape of the board changes in a way
+/// `#[serde(default)]` can't cover, and add a step to `migrate` that
+/// upgrades the previous version.
+pub const SCHEMA_VERSION: u64 = 3;
+
+const APP_DIR: &str = "smart-goal-kanban";
+const BOARD_FILE: &str = "board.json";
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    MissingVersion,
+    UnsupportedVersion(u64),
+}
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::Io(err) => write!(f, "could not access board file: {}", err),
+            StorageError::Json(err) => write!(f, "board file is not valid: {}", err),
+            StorageError::MissingVersion => {
+                write!(f, "board file has no schema version")
+            },
+            StorageError::UnsupportedVersion(version) => write!(
+                f,
+                "board file has schema version {}, this build only understands up to {}",
+                version, SCHEMA_VERSION
+            ),
+        }
+    }
+}
+impl Error for StorageError {}
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self { StorageError::Io(err) }
+}
+impl From<serde_json::Error> for StorageError {
+    fn from(err: serde_json::Error) -> Self { StorageError::Json(err) }
+}
+
+// What gets written to disk:
+#[derive(Serialize)]
+struct FileRef<'a> {
+    version: u64,
+    // The tab that was open:
+    open: usize,
+    boards: Vec<BoardRef<'a>>,
+}
+#[derive(Serialize)]
+struct BoardRef<'a> {
+    name: &'a str,
+    lanes: Vec<LaneRef<'a>>,
+    archive: &'a [ArchivedCard],
+    filters: &'a [SavedFilter],
+}
+#[derive(Serialize)]
+struct LaneRef<'a> {
+    name: &'a str,
+    cards: &'a [Card],
+    sort: SortMode,
+}
+
+// What gets read back, after migrating to SCHEMA_VERSION:
+#[derive(Deserialize)]
+struct File {
+    #[serde(default)]
+    open: usize,
+    boards: Vec<BoardFile>,
+}
+#[derive(Deserialize)]
+struct BoardFile {
+    name: String,
+    lanes: Vec<LaneFile>,
+    // Boards saved before there was an archive don't have one:
+    #[serde(default)]
+    archive: Vec<ArchivedCard>,
+    #[serde(default)]
+    filters: Vec<SavedFilter>,
+}
+#[derive(Deserialize)]
+struct LaneFile {
+    name: String,
+    cards: Vec<Card>,
+    #[serde(default)]
+    sort: SortMode,
+}
+
+/// Everything in a board file: the boards, named by `names`, and
+/// which of them was open.
+pub struct SavedBoards {
+    pub names: Vec<String>,
+    pub boards: Vec<Board>,
+    pub open: usize,
+}
+
+/// `$XDG_DATA_HOME/smart-goal-kanban/board.json`,
+/// falling back to `~/.local/share` when XDG_DATA_HOME is not set.
+pub fn default_path() -> PathBuf {
+    let data_home = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var_os("HOME").unwrap_or_default();
+            PathBuf::from(home).join(".local").join("share")
+        },
+    };
+    data_home.join(APP_DIR).join(BOARD_FILE)
+}
+
+/// Returns `None` if there is no board file yet.
+pub fn load(path: &Path) -> Result<Option<SavedBoards>, StorageError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let value: Value = serde_json::from_str(&contents)?;
+    let version = value.get("version")
+        .and_then(Value::as_u64)
+        .ok_or(StorageError::MissingVersion)?;
+
+    let file: File = serde_json::from_value(migrate(value, version)?)?;
+    let mut saved = SavedBoards { names: Vec::new(), boards: Vec::new(), open: file.open };
+    for board in file.boards {
+        saved.names.push(board.name);
+        saved.boards.push(Board {
+            lanes: board.lanes
+                .into_iter()
+                .map(|lane| Lane {
+                    sort: lane.sort,
+                    ..Lane::with_cards(&lane.name, lane.cards)
+                })
+                .collect(),
+            archive: board.archive,
+            filters: board.filters,
+            ..Board::default()
+        });
+    }
+    Ok(Some(saved))
+}
+
+/// When the board file was last written, by this app or anything else.
+/// `None` if there is no board file yet.
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Write every board of `app` to `path`.
+pub fn save(path: &Path, app: &App) -> Result<(), StorageError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let boards = app.tabs.titles
+        .iter()
+        .zip(app.boards.iter())
+        .enumerate()
+        .map(|(index, (name, board))| {
+            // The open board's contents are in the app itself:
+            if index == app.tabs.index {
+                BoardRef { name, lanes: lanes(&app.lanes), archive: &app.archive, filters: &app.filters }
+            } else {
+                BoardRef { name, lanes: lanes(&board.lanes), archive: &board.archive, filters: &board.filters }
+            }
+        })
+        .collect();
+    let file = FileRef {
+        version: SCHEMA_VERSION,
+        open: app.tabs.index,
+        boards,
+    };
+    // Write next to the real file and rename over it,
+    // so a crash mid-write can't leave a half written board behind:
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(&file)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn lanes(lanes: &[Lane]) -> Vec<LaneRef<'_>> {
+    lanes.iter()
+        .map(|lane| LaneRef { name: &lane.name, cards: &lane.cards.items, sort: lane.sort })
+        .collect()
+}
+
+// Upgrade an older board one version at a time until it matches SCHEMA_VERSION.
+fn migrate(mut value: Value, version: u64) -> Result<Value, StorageError> {
+    if version == 0 || version > SCHEMA_VERSION {
+        return Err(StorageError::UnsupportedVersion(version));
+    }
+    if version < 2 {
+        value = v1_to_v2(value);
+    }
+    if version < 3 {
+        value = v2_to_v3(value);
+    }
+    Ok(value)
+}
+
+// v1 lanes were bare lists of cards, named by their position on the board.
+fn v1_to_v2(value: Value) -> Value {
+    let lanes: Vec<Value> = match value.get("lanes") {
+        Some(Value::Array(lanes)) => lanes.clone(),
+        _ => Vec::new(),
+    };
+    let lanes: Vec<Value> = lanes
+        .into_iter()
+        .enumerate()
+        .map(|(index, cards)| {
+            let name = match DEFAULT_LANES.get(index) {
+                Some(name) => name.to_string(),
+                None => format!("Lane {}", index + 1),
+            };
+            json!({ "name": name, "cards": cards })
+        })
+        .collect();
+    json!({ "version": 2, "lanes": lanes })
+}
+
+// v2 files held a single board, which becomes the first (and only) tab.
+fn v2_to_v3(mut value: Value) -> Value {
+    let mut board = json!({ "name": DEFAULT_BOARD });
+    for key in ["lanes", "archive", "filters"].iter() {
+        if let Some(field) = value.get_mut(*key) {
+            board[*key] = field.take();
+        }
+    }
+    json!({ "version": 3, "open": 0, "boards": [board] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::util::app::Priority;
+
+    // A board file of its own in the temp dir, for each test
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("{}-{}", APP_DIR, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn saved_boards_load_back_the_same() {
+        let path = temp_path("round-trip.json");
+        let mut app = App::default();
+        app.add_card("Write the report");
+        app.update_card(|card| {
+            card.priority = Priority::High;
+            card.tags = vec![String::from("work")];
+            card.description = vec![String::from("[x] Outline")];
+        });
+        app.cycle_sort();
+        app.add_card("Old news");
+        app.archive_card();
+        app.filters.push(SavedFilter { name: String::from("work"), query: String::from("tag:work") });
+        app.add_board("Home");
+        app.add_card("Book flights");
+        save(&path, &app).unwrap();
+
+        let saved = load(&path).unwrap().unwrap();
+        assert_eq!(saved.names, vec![DEFAULT_BOARD, "Home"]);
+        assert_eq!(saved.open, 1);
+        let home = App::with_boards(saved.names, saved.boards, saved.open);
+        assert!(home.lanes[0].cards.items == app.lanes[0].cards.items);
+
+        let mut main = home.clone();
+        main.switch_board(0);
+        app.switch_board(0);
+        assert_eq!(main.lanes.len(), app.lanes.len());
+        assert_eq!(main.lanes[0].sort, SortMode::Priority);
+        assert!(main.lanes[0].cards.items == app.lanes[0].cards.items);
+        assert!(main.archive == app.archive);
+        assert!(main.filters == app.filters);
+        assert!(!main.dirty);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn v1_files_load_through_the_migration() {
+        let path = temp_path("v1.json");
+        fs::write(&path, r#"{ "version": 1, "lanes": [[{ "title": "Write the report", "priority": 2 }], [], [], []] }"#).unwrap();
+        let saved = load(&path).unwrap().unwrap();
+        assert_eq!(saved.names, vec![DEFAULT_BOARD]);
+        let lanes = &saved.boards[0].lanes;
+        let names: Vec<&str> = lanes.iter().map(|lane| lane.name.as_str()).collect();
+        assert_eq!(names, DEFAULT_LANES);
+        assert_eq!(lanes[0].cards.items[0].title, "Write the report");
+        assert_eq!(lanes[0].cards.items[0].priority, Priority::High);
+
+        fs::write(&path, r#"{ "lanes": [] }"#).unwrap();
+        assert!(matches!(load(&path), Err(StorageError::MissingVersion)));
+        fs::remove_file(&path).unwrap();
+        assert!(load(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn old_boards_become_the_first_tab() {
+        let v1 = json!({ "version": 1, "lanes": [[{ "title": "Write the report" }], []] });
+        let file: File = serde_json::from_value(migrate(v1, 1).unwrap()).unwrap();
+        assert_eq!(file.boards.len(), 1);
+        assert_eq!(file.boards[0].name, DEFAULT_BOARD);
+        assert_eq!(file.boards[0].lanes[0].name, DEFAULT_LANES[0]);
+        assert_eq!(file.boards[0].lanes[0].cards[0].title, "Write the report");
+
+        let v2 = json!({
+            "version": 2,
+            "lanes": [{ "name": "Todo", "cards": [] }],
+            "filters": [{ "name": "urgent", "query": "priority:urgent" }],
+        });
+        let file: File = serde_json::from_value(migrate(v2, 2).unwrap()).unwrap();
+        assert_eq!(file.open, 0);
+        assert_eq!(file.boards[0].filters[0].name, "urgent");
+        assert!(migrate(json!({ "version": 4 }), 4).is_err());
+    }
+}